@@ -1,14 +1,19 @@
-use crate::core::record::{self, Quest, QuestState, RecordingMeta, MonitorInfo};
-use tauri::{AppHandle, State};
+use crate::core::capture::CaptureTarget;
+use crate::core::retention::{self, RetentionCandidate};
+use crate::core::record::{self, Quest, QuestState, RecordingMeta, RecordingZip, MonitorInfo};
+use crate::tools::audio::AudioCapture;
+use tauri::{ipc::Response, AppHandle, State};
 
 #[tauri::command]
 pub async fn start_recording(
     app: AppHandle,
-    quest_state: State<'_, QuestState>,
     quest: Option<Quest>,
-    display: Option<MonitorInfo>
+    display: Option<MonitorInfo>,
+    displays: Option<Vec<MonitorInfo>>,
+    target: Option<CaptureTarget>,
+    audio: Option<AudioCapture>,
 ) -> Result<(), String> {
-    record::start_recording(app, quest_state, quest, display).await
+    record::start_recording(app, quest, display, displays, target, audio).await
 }
 
 #[tauri::command]
@@ -20,6 +25,16 @@ pub async fn stop_recording(
     record::stop_recording(app, quest_state, reason).await
 }
 
+#[tauri::command]
+pub async fn pause_recording(app: AppHandle) -> Result<(), String> {
+    record::pause_recording(app).await
+}
+
+#[tauri::command]
+pub async fn resume_recording(app: AppHandle) -> Result<(), String> {
+    record::resume_recording(app).await
+}
+
 #[tauri::command]
 pub async fn get_recording_state() -> Result<String, String> {
     record::get_recording_state().await
@@ -71,11 +86,32 @@ pub async fn delete_recording(app: AppHandle, recording_id: String) -> Result<()
     record::delete_recording(app, recording_id).await
 }
 
+/// Path and size of the archive built on disk; remove it with `delete_recording_zip` once done
 #[tauri::command]
-pub async fn create_recording_zip(app: AppHandle, recording_id: String) -> Result<Vec<u8>, String> {
+pub async fn create_recording_zip_file(
+    app: AppHandle,
+    recording_id: String,
+) -> Result<RecordingZip, String> {
     record::create_recording_zip(app, recording_id).await
 }
 
+/// Raw bytes of one chunk of the archive, received as an `ArrayBuffer`
+#[tauri::command]
+pub async fn read_recording_zip_chunk(
+    app: AppHandle,
+    recording_id: String,
+    offset: u64,
+    length: u64,
+) -> Result<Response, String> {
+    let chunk = record::read_recording_zip_chunk(&app, &recording_id, offset, length)?;
+    Ok(Response::new(chunk))
+}
+
+#[tauri::command]
+pub async fn delete_recording_zip(app: AppHandle, recording_id: String) -> Result<(), String> {
+    record::delete_recording_zip(app, recording_id).await
+}
+
 #[tauri::command]
 pub async fn export_recording_zip(id: String, app: AppHandle) -> Result<String, String> {
     record::export_recording_zip(id, app).await
@@ -92,3 +128,22 @@ pub async fn get_current_quest(
 ) -> Result<Option<Quest>, String> {
     record::get_current_quest(quest_state).await
 }
+
+#[tauri::command]
+pub async fn set_recording_pinned(
+    app: AppHandle,
+    recording_id: String,
+    pinned: bool,
+) -> Result<(), String> {
+    retention::set_recording_pinned(&app, &recording_id, pinned)
+}
+
+#[tauri::command]
+pub async fn mark_recording_uploaded(app: AppHandle, recording_id: String) -> Result<(), String> {
+    retention::mark_recording_uploaded(&app, &recording_id)
+}
+
+#[tauri::command]
+pub async fn preview_retention(app: AppHandle) -> Result<Vec<RetentionCandidate>, String> {
+    retention::preview_retention(&app)
+}
@@ -1,22 +1,18 @@
+use crate::core::{archive, input_log, record};
+use crate::utils::storage;
 use std::{
-    io::{Cursor, Write},
+    fs::File,
+    io::{BufWriter, Write},
     path::Path,
 };
-use tauri::Manager;
 use tauri_plugin_dialog::DialogExt;
 use zip::{write::FileOptions, ZipWriter};
 
-#[tauri::command]
-pub async fn export_recordings(app: tauri::AppHandle) -> Result<String, String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
-
-    // Create a buffer to store the zip file
-    let buf = Cursor::new(Vec::new());
-    let mut zip = ZipWriter::new(buf);
+// Streams every recording into a zip at `zip_path`
+fn write_recordings_zip(recordings_dir: &Path, zip_path: &Path) -> Result<(), String> {
+    let zip_file =
+        File::create(zip_path).map_err(|e| format!("Failed to create zip file: {}", e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(zip_file));
     let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
 
     // Add recordings folder contents to zip
@@ -25,7 +21,7 @@ pub async fn export_recordings(app: tauri::AppHandle) -> Result<String, String>
 
         // Helper function to recursively add files and directories to the zip
         fn add_dir_to_zip(
-            zip: &mut ZipWriter<Cursor<Vec<u8>>>,
+            zip: &mut ZipWriter<BufWriter<File>>,
             options: FileOptions,
             src_dir: &Path,
             base_path: &Path,
@@ -44,14 +40,7 @@ pub async fn export_recordings(app: tauri::AppHandle) -> Result<String, String>
 
                 if path.is_file() {
                     // Add file to zip
-                    zip.start_file(relative_path_str.as_ref(), options)
-                        .map_err(|e| format!("Failed to add file to zip: {}", e))?;
-
-                    let contents = std::fs::read(&path)
-                        .map_err(|e| format!("Failed to read file contents: {}", e))?;
-
-                    zip.write_all(&contents)
-                        .map_err(|e| format!("Failed to write file contents to zip: {}", e))?;
+                    archive::add_file_to_zip(zip, relative_path_str.as_ref(), &path)?;
                 } else if path.is_dir() {
                     // Create directory entry in zip
                     let dir_path = format!("{}/", relative_path_str);
@@ -66,13 +55,18 @@ pub async fn export_recordings(app: tauri::AppHandle) -> Result<String, String>
         }
 
         // Start recursively adding files and directories
-        add_dir_to_zip(&mut zip, options, &recordings_dir, &recordings_dir)?;
+        add_dir_to_zip(&mut zip, options, recordings_dir, recordings_dir)?;
     }
 
-    let buf = zip
-        .finish()
+    zip.finish()
         .map_err(|e| format!("Failed to finalize zip: {}", e))?
-        .into_inner();
+        .flush()
+        .map_err(|e| format!("Failed to flush zip file: {}", e))
+}
+
+#[tauri::command]
+pub async fn export_recordings(app: tauri::AppHandle) -> Result<String, String> {
+    let recordings_dir = storage::recordings_dir(&app)?;
 
     let selected_dir = app.dialog().file().blocking_pick_folder();
 
@@ -82,11 +76,37 @@ pub async fn export_recordings(app: tauri::AppHandle) -> Result<String, String>
         let dir_path_str = dir_path.to_string();
         let file_path = Path::new(&dir_path_str).join("history.zip");
 
-        // Write the buffer to the file
-        std::fs::write(&file_path, buf).map_err(|e| format!("Failed to write zip file: {}", e))?;
+        // Write the zip straight to the chosen folder, removing it if that fails
+        if let Err(e) = write_recordings_zip(&recordings_dir, &file_path) {
+            let _ = std::fs::remove_file(&file_path);
+            return Err(e);
+        }
 
         Ok(file_path.to_string_lossy().into_owned())
     } else {
         Ok("".to_string())
     }
 }
+
+#[tauri::command]
+pub async fn get_recordings_dir(app: tauri::AppHandle) -> Result<String, String> {
+    Ok(storage::recordings_dir(&app)?.to_string_lossy().to_string())
+}
+
+/// `storage_root` of `None` moves the recordings back to the app data folder
+#[tauri::command]
+pub async fn move_recordings(
+    app: tauri::AppHandle,
+    storage_root: Option<String>,
+) -> Result<String, String> {
+    if record::is_recording() {
+        return Err("Cannot move recordings while a recording is in progress".to_string());
+    }
+    storage::move_recordings(&app, storage_root)
+}
+
+/// JSON Schema of a single input_log.jsonl line
+#[tauri::command]
+pub fn get_input_log_schema() -> serde_json::Value {
+    input_log::input_log_schema()
+}
@@ -1,5 +1,9 @@
 use tauri::AppHandle;
-use crate::utils::settings::Settings;
+use crate::tools::ffmpeg;
+use crate::utils::settings::{
+    EncodingProfile, InputSampling, PiiScrubbing, RecordingLimits, RedactionSettings,
+    RetentionPolicy, Settings,
+};
 
 #[tauri::command]
 pub fn get_upload_data_allowed(app: AppHandle) -> bool {
@@ -24,3 +28,104 @@ pub fn set_onboarding_complete(app: AppHandle, confirmed: bool) -> Result<(), St
     settings.onboarding_complete = confirmed;
     settings.save(&app)
 }
+
+#[tauri::command]
+pub fn get_encoding_profile(app: AppHandle) -> EncodingProfile {
+    Settings::load(&app).encoding
+}
+
+#[tauri::command]
+pub fn set_encoding_profile(app: AppHandle, profile: EncodingProfile) -> Result<(), String> {
+    profile.validate()?;
+
+    ffmpeg::init_ffmpeg()?;
+    if !ffmpeg::probe_capabilities()?.has_encoder(&profile.codec) {
+        return Err(format!(
+            "The installed FFmpeg build does not include the {} encoder",
+            profile.codec
+        ));
+    }
+
+    let mut settings = Settings::load(&app);
+    settings.encoding = profile;
+    settings.save(&app)
+}
+
+#[tauri::command]
+pub fn get_available_codecs() -> Result<Vec<String>, String> {
+    ffmpeg::init_ffmpeg()?;
+    Ok(ffmpeg::probe_capabilities()?.codecs)
+}
+
+#[tauri::command]
+pub fn get_recording_limits(app: AppHandle) -> RecordingLimits {
+    Settings::load(&app).limits
+}
+
+#[tauri::command]
+pub fn set_recording_limits(app: AppHandle, limits: RecordingLimits) -> Result<(), String> {
+    limits.validate()?;
+
+    let mut settings = Settings::load(&app);
+    settings.limits = limits;
+    settings.save(&app)
+}
+
+#[tauri::command]
+pub fn get_retention_policy(app: AppHandle) -> RetentionPolicy {
+    Settings::load(&app).retention
+}
+
+#[tauri::command]
+pub fn set_retention_policy(app: AppHandle, policy: RetentionPolicy) -> Result<(), String> {
+    policy.validate()?;
+
+    let mut settings = Settings::load(&app);
+    settings.retention = policy;
+    settings.save(&app)
+}
+
+#[tauri::command]
+pub fn get_input_sampling(app: AppHandle) -> InputSampling {
+    Settings::load(&app).input_sampling
+}
+
+/// Takes effect from the next recording
+#[tauri::command]
+pub fn set_input_sampling(app: AppHandle, sampling: InputSampling) -> Result<(), String> {
+    sampling.validate()?;
+
+    let mut settings = Settings::load(&app);
+    settings.input_sampling = sampling;
+    settings.save(&app)
+}
+
+#[tauri::command]
+pub fn get_redaction_settings(app: AppHandle) -> RedactionSettings {
+    Settings::load(&app).redaction
+}
+
+/// Takes effect from the next recording
+#[tauri::command]
+pub fn set_redaction_settings(app: AppHandle, redaction: RedactionSettings) -> Result<(), String> {
+    redaction.validate()?;
+
+    let mut settings = Settings::load(&app);
+    settings.redaction = redaction;
+    settings.save(&app)
+}
+
+#[tauri::command]
+pub fn get_pii_scrubbing(app: AppHandle) -> PiiScrubbing {
+    Settings::load(&app).pii_scrubbing
+}
+
+/// Takes effect from the next recording
+#[tauri::command]
+pub fn set_pii_scrubbing(app: AppHandle, pii_scrubbing: PiiScrubbing) -> Result<(), String> {
+    pii_scrubbing.validate()?;
+
+    let mut settings = Settings::load(&app);
+    settings.pii_scrubbing = pii_scrubbing;
+    settings.save(&app)
+}
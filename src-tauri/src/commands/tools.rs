@@ -1,5 +1,5 @@
 use std::path::PathBuf;
-use crate::tools::{axtree, ffmpeg, pipeline};
+use crate::tools::{audio, axtree, ffmpeg, pipeline};
 use log::error;
 use serde_json;
 use std::sync::{Arc, Mutex};
@@ -113,3 +113,15 @@ pub async fn check_tools() -> Result<serde_json::Value, String> {
         "pipeline": pipeline_exists
     }))
 }
+
+#[tauri::command]
+pub async fn get_ffmpeg_capabilities() -> Result<ffmpeg::FFmpegCapabilities, String> {
+    ffmpeg::init_ffmpeg()?;
+    ffmpeg::probe_capabilities()
+}
+
+#[tauri::command]
+pub async fn list_audio_devices() -> Result<Vec<String>, String> {
+    ffmpeg::init_ffmpeg()?;
+    audio::list_audio_devices()
+}
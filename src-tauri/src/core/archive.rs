@@ -1,6 +1,10 @@
 use std::fs;
 use std::io;
 use std::path::Path;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+// Entries this size or larger need Zip64 headers
+const ZIP64_THRESHOLD: u64 = u32::MAX as u64;
 
 /// Extracts a file from a zip archive
 pub fn extract_from_zip(
@@ -104,6 +108,35 @@ pub fn extract_from_tar_xz(
     Ok(false) // File not found
 }
 
+/// Options for an entry of an exported archive: JSON is deflated, while videos are already
+/// compressed and stored as they are
+pub fn zip_entry_options(name: &str, size: u64) -> FileOptions {
+    let method = if name.ends_with(".json") || name.ends_with(".jsonl") {
+        CompressionMethod::Deflated
+    } else {
+        CompressionMethod::Stored
+    };
+    FileOptions::default()
+        .compression_method(method)
+        .large_file(size >= ZIP64_THRESHOLD)
+}
+
+/// Streams the file at `path` into the archive as `name`, returning the bytes copied
+pub fn add_file_to_zip<W: io::Write + io::Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    path: &Path,
+) -> Result<u64, String> {
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to get metadata for {}: {}", name, e))?
+        .len();
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", name, e))?;
+
+    zip.start_file(name, zip_entry_options(name, size))
+        .map_err(|e| format!("Failed to add {} to zip: {}", name, e))?;
+    io::copy(&mut file, zip).map_err(|e| format!("Failed to write {} to zip: {}", name, e))
+}
+
 /// Creates the output file for the binary
 fn create_output_file(output_path: &Path) -> Result<fs::File, String> {
     fs::File::create(output_path).map_err(|e| {
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use xcap::Window;
+
+// How often a tracked window's position is refreshed
+const TRACK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// What a recording captures when it is not a whole monitor
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CaptureTarget {
+    Region {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+    Window {
+        id: u32,
+    },
+}
+
+/// Screen area covered by a capture target, in desktop coordinates
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct CaptureRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A capture target looked up against the current desktop
+pub struct ResolvedTarget {
+    pub target: CaptureTarget,
+    pub area: CaptureRect,
+    pub title: Option<String>,
+}
+
+impl ResolvedTarget {
+    pub fn window_id(&self) -> Option<u32> {
+        match self.target {
+            CaptureTarget::Window { id } => Some(id),
+            CaptureTarget::Region { .. } => None,
+        }
+    }
+}
+
+pub fn resolve_target(target: &CaptureTarget) -> Result<ResolvedTarget, String> {
+    // Wayland hides window geometry from clients; the portal's window picker takes over
+    #[cfg(target_os = "linux")]
+    if crate::tools::wayland::is_wayland_session() {
+        return match target {
+            CaptureTarget::Window { .. } => Ok(ResolvedTarget {
+                target: target.clone(),
+                area: CaptureRect {
+                    x: 0,
+                    y: 0,
+                    width: 0,
+                    height: 0,
+                },
+                title: None,
+            }),
+            CaptureTarget::Region { .. } => {
+                Err("Region capture is not supported on Wayland".to_string())
+            }
+        };
+    }
+
+    let (area, title) = match *target {
+        CaptureTarget::Region {
+            x,
+            y,
+            width,
+            height,
+        } => (
+            CaptureRect {
+                x,
+                y,
+                width,
+                height,
+            },
+            None,
+        ),
+        CaptureTarget::Window { id } => {
+            let window = find_window(id)?;
+            if window.is_minimized().unwrap_or(false) {
+                return Err("The selected window is minimized".to_string());
+            }
+            (window_rect(&window)?, window.title().ok())
+        }
+    };
+
+    // yuv420p needs even dimensions, so drop the odd row/column instead of failing
+    let area = CaptureRect {
+        width: area.width & !1,
+        height: area.height & !1,
+        ..area
+    };
+    if area.width < 16 || area.height < 16 {
+        return Err(format!(
+            "Capture area {}x{} is too small to record",
+            area.width, area.height
+        ));
+    }
+
+    log::info!(
+        "[Capture] Resolved {:?} to {}x{} at ({}, {})",
+        target,
+        area.width,
+        area.height,
+        area.x,
+        area.y
+    );
+    Ok(ResolvedTarget {
+        target: target.clone(),
+        area,
+        title,
+    })
+}
+
+fn find_window(id: u32) -> Result<Window, String> {
+    Window::all()
+        .map_err(|e| format!("Failed to list windows: {}", e))?
+        .into_iter()
+        .find(|w| w.id().ok() == Some(id))
+        .ok_or_else(|| format!("Window {} not found", id))
+}
+
+fn window_rect(window: &Window) -> Result<CaptureRect, String> {
+    let geometry_error = |e: xcap::XCapError| format!("Failed to read window geometry: {}", e);
+    Ok(CaptureRect {
+        x: window.x().map_err(geometry_error)?,
+        y: window.y().map_err(geometry_error)?,
+        width: window.width().map_err(geometry_error)?,
+        height: window.height().map_err(geometry_error)?,
+    })
+}
+
+/// Follows a window while it is recorded so input coordinates can be made relative to it
+struct WindowTracker {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+// Global state for window tracking
+lazy_static::lazy_static! {
+    static ref WINDOW_TRACKER_STATE: Mutex<Option<WindowTracker>> = Mutex::new(None);
+}
+
+/// Polls the window's geometry into `area` until `stop_window_tracker` is called
+pub fn start_window_tracker(id: u32, area: Arc<Mutex<CaptureRect>>) -> Result<(), String> {
+    log::info!("[Capture] Tracking window {}", id);
+    let mut state = WINDOW_TRACKER_STATE.lock().map_err(|e| e.to_string())?;
+    if state.is_some() {
+        return Ok(());
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    let running_clone = running.clone();
+    let thread = thread::spawn(move || {
+        while running_clone.load(Ordering::SeqCst) {
+            // Keep the last known position if the window disappears for a moment
+            if let Ok(rect) = find_window(id).and_then(|w| window_rect(&w)) {
+                if let Ok(mut area) = area.lock() {
+                    area.x = rect.x;
+                    area.y = rect.y;
+                }
+            }
+            thread::sleep(TRACK_INTERVAL);
+        }
+    });
+
+    *state = Some(WindowTracker {
+        running,
+        thread: Some(thread),
+    });
+    Ok(())
+}
+
+pub fn stop_window_tracker() -> Result<(), String> {
+    let mut state = WINDOW_TRACKER_STATE.lock().map_err(|e| e.to_string())?;
+    if let Some(mut tracker) = state.take() {
+        log::info!("[Capture] Stopping window tracker");
+        tracker.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = tracker.thread.take() {
+            let _ = thread.join();
+        }
+    }
+    Ok(())
+}
@@ -1,19 +1,24 @@
+use crate::core::input_log::InputEvent;
 use crate::core::record;
+use crate::utils::settings::{InputSampling, MoveSampling};
 use log::{error, info};
 use rdev::{listen, Event as RdevEvent, EventType as RdevEventType};
-use serde::Serialize;
 use std::{
+    collections::BTreeSet,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        Arc,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 use tauri::Emitter;
 use tauri::Runtime;
 
+/// Input capture for one recording session; dropping it stops the listener
 pub struct InputListener {
     running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
     threads: Vec<JoinHandle<()>>,
 }
 
@@ -21,12 +26,23 @@ impl InputListener {
     pub fn new() -> Self {
         Self {
             running: Arc::new(AtomicBool::new(true)),
+            paused: Arc::new(AtomicBool::new(false)),
             threads: Vec::new(),
         }
     }
 
+    pub fn set_paused(&self, paused: bool) {
+        info!(
+            "[Input] {} input listener",
+            if paused { "Pausing" } else { "Resuming" }
+        );
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
     pub fn stop(&mut self) {
-        self.running.store(false, Ordering::SeqCst);
+        if self.running.swap(false, Ordering::SeqCst) {
+            info!("[Input] Stopping input listener");
+        }
         // Don't wait for threads since they might be blocked in rdev listen()
         self.threads.clear();
     }
@@ -38,44 +54,241 @@ impl Drop for InputListener {
     }
 }
 
-// Global state for input listening
-lazy_static::lazy_static! {
-    static ref INPUT_LISTENER_STATE: Arc<Mutex<Option<InputListener>>> = Arc::new(Mutex::new(None));
+// Modifier for a key, by the name either backend gives it (rdev on the left, multiinput on
+// the right)
+fn modifier_name(key: &str) -> Option<&'static str> {
+    match key {
+        "ControlLeft" | "ControlRight" | "LeftCtrl" | "RightCtrl" => Some("ctrl"),
+        "Alt" | "LeftAlt" => Some("alt"),
+        "AltGr" | "RightAlt" => Some("altgr"),
+        "ShiftLeft" | "ShiftRight" | "Shift" => Some("shift"),
+        "MetaLeft" | "MetaRight" => Some("meta"),
+        _ => None,
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct InputEvent {
-    pub event: String,
-    pub data: serde_json::Value,
+// "KeyC" -> "C", "Num1" -> "1"; other names are kept as they are
+fn key_label(key: &str) -> &str {
+    for prefix in ["Key", "Num"] {
+        if let Some(rest) = key.strip_prefix(prefix) {
+            if rest.chars().count() == 1 {
+                return rest;
+            }
+        }
+    }
+    key
+}
+
+/// Tracks which modifiers are held so key events can carry them, and turns key presses
+/// made while Ctrl, Alt or Meta is held into `shortcut` events
+#[derive(Default)]
+struct Keyboard {
+    // Names of the modifier keys currently down, so releasing one of two Shift keys keeps
+    // Shift active
+    held: BTreeSet<String>,
 }
 
-impl InputEvent {
-    pub fn new(event: &str, data: serde_json::Value) -> Self {
+impl Keyboard {
+    fn modifiers(&self) -> Vec<String> {
+        let active: BTreeSet<&str> = self.held.iter().filter_map(|k| modifier_name(k)).collect();
+        ["ctrl", "alt", "altgr", "shift", "meta"]
+            .into_iter()
+            .filter(|m| active.contains(m))
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn key_event(
+        &mut self,
+        key: String,
+        pressed: bool,
+        scancode: Option<u32>,
+        text: Option<String>,
+    ) -> Vec<InputEvent> {
+        let is_modifier = modifier_name(&key).is_some();
+        if is_modifier {
+            if pressed {
+                self.held.insert(key.clone());
+            } else {
+                self.held.remove(&key);
+            }
+        }
+
+        let modifiers = self.modifiers();
+        if !pressed {
+            return vec![InputEvent::Keyup {
+                key,
+                modifiers,
+                scancode,
+            }];
+        }
+
+        // AltGr types characters on many layouts, it doesn't make a shortcut
+        let is_shortcut = !is_modifier
+            && !modifiers.iter().any(|m| m == "altgr")
+            && modifiers
+                .iter()
+                .any(|m| m == "ctrl" || m == "alt" || m == "meta");
+        let shortcut = is_shortcut.then(|| {
+            let mut keys: Vec<String> = modifiers
+                .iter()
+                .map(|m| {
+                    let mut label = m.clone();
+                    label[..1].make_ascii_uppercase();
+                    label
+                })
+                .collect();
+            keys.push(key_label(&key).to_string());
+            InputEvent::Shortcut {
+                keys: keys.join("+"),
+                modifiers: modifiers.clone(),
+                key: key_label(&key).to_string(),
+            }
+        });
+
+        // Control characters (e.g. Ctrl+C producing U+0003) aren't typed text
+        let text = text.filter(|text| !text.is_empty() && !text.chars().any(char::is_control));
+        let mut events = vec![InputEvent::Keydown {
+            key,
+            modifiers,
+            scancode,
+            text,
+        }];
+        events.extend(shortcut);
+        events
+    }
+}
+
+// `cursor` is the last position reported by the listener
+fn mouse_button(button: String, pressed: bool, cursor: Option<(f64, f64)>) -> InputEvent {
+    let (x, y) = (cursor.map(|c| c.0), cursor.map(|c| c.1));
+    if pressed {
+        InputEvent::Mousedown {
+            button,
+            x,
+            y,
+            screen_x: None,
+            screen_y: None,
+        }
+    } else {
+        InputEvent::Mouseup {
+            button,
+            x,
+            y,
+            screen_x: None,
+            screen_y: None,
+        }
+    }
+}
+
+/// Drops mouse moves arriving faster than `max_hz` or closer than `min_distance_px` to the
+/// last one kept. The latest dropped move is held back and sent before the next other
+/// event, so a click always follows the final cursor position.
+struct MoveSampler {
+    sampling: MoveSampling,
+    // When and where the last move was kept
+    last: Option<(Instant, (f64, f64))>,
+    pending: Option<InputEvent>,
+}
+
+impl MoveSampler {
+    fn new(sampling: MoveSampling) -> Self {
         Self {
-            event: event.to_string(),
-            data,
+            sampling,
+            last: None,
+            pending: None,
+        }
+    }
+
+    fn sample(&mut self, event: InputEvent) -> Option<InputEvent> {
+        let Some((x, y)) = event.position() else {
+            return Some(event);
+        };
+
+        let now = Instant::now();
+        let keep = match self.last {
+            Some((kept_at, (last_x, last_y))) => {
+                let interval = self.sampling.max_hz.map_or(Duration::ZERO, |hz| {
+                    Duration::from_secs_f64(1.0 / hz as f64)
+                });
+                now - kept_at >= interval
+                    && (x - last_x).hypot(y - last_y) >= self.sampling.min_distance_px
+            }
+            None => true,
+        };
+
+        if keep {
+            self.last = Some((now, (x, y)));
+            self.pending = None;
+            Some(event)
+        } else {
+            self.pending = Some(event);
+            None
         }
     }
 
-    pub fn to_log_entry(&self) -> serde_json::Value {
-        serde_json::json!({
-            "event": self.event,
-            "data": self.data,
-            "time": chrono::Local::now().timestamp_millis()
-        })
+    fn take_pending(&mut self) -> Option<InputEvent> {
+        let event = self.pending.take()?;
+        self.last = event.position().map(|position| (Instant::now(), position));
+        Some(event)
     }
 }
 
-pub fn start_input_listener<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Result<(), String> {
-    info!("[Input] Starting input listener");
-    // Check if already listening
-    let mut state = INPUT_LISTENER_STATE.lock().map_err(|e| e.to_string())?;
-    if state.is_some() {
-        return Ok(()); // Already listening
+// Sends events to the frontend and the active recording's log, sampling mouse moves
+// separately for each
+struct Dispatcher<R: Runtime> {
+    app_handle: tauri::AppHandle<R>,
+    emitted: MoveSampler,
+    logged: MoveSampler,
+}
+
+impl<R: Runtime> Dispatcher<R> {
+    fn new(app_handle: tauri::AppHandle<R>, sampling: &InputSampling) -> Self {
+        Self {
+            app_handle,
+            emitted: MoveSampler::new(sampling.emit),
+            logged: MoveSampler::new(sampling.log),
+        }
     }
 
+    fn send(&mut self, event: InputEvent) {
+        if let InputEvent::Mousemove { .. } = event {
+            if let Some(event) = self.emitted.sample(event.clone()) {
+                self.emit(&event);
+            }
+            if let Some(event) = self.logged.sample(event) {
+                let _ = record::log_input(event);
+            }
+            return;
+        }
+
+        if let Some(event) = self.emitted.take_pending() {
+            self.emit(&event);
+        }
+        if let Some(event) = self.logged.take_pending() {
+            let _ = record::log_input(event);
+        }
+        self.emit(&event);
+        // Log the input event
+        let _ = record::log_input(event);
+    }
+
+    fn emit(&self, event: &InputEvent) {
+        if let Err(e) = self.app_handle.emit("input-event", event) {
+            error!("Failed to emit input event: {}", e);
+        }
+    }
+}
+
+/// `sampling` limits how many mouse moves are logged and emitted as `input-event`
+pub fn start_input_listener<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    sampling: InputSampling,
+) -> Result<InputListener, String> {
+    info!("[Input] Starting input listener");
     let mut input_listener = InputListener::new();
     let running = input_listener.running.clone();
+    let paused = input_listener.paused.clone();
     let other_app_handle = app_handle.clone();
 
     // Platform-specific input handling
@@ -83,8 +296,25 @@ pub fn start_input_listener<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Resu
     #[cfg(target_os = "windows")]
     {
         use multiinput::*;
+        // Raw input only has relative motion, clicks take the position from rdev's thread
+        let cursor = Arc::new(std::sync::Mutex::new(None::<(f64, f64)>));
+        let cursor_clone = cursor.clone();
+        // Shared by both threads so held-back moves are flushed before clicks
+        let dispatcher = Arc::new(std::sync::Mutex::new(Dispatcher::new(
+            other_app_handle,
+            &sampling,
+        )));
+        let dispatcher_clone = dispatcher.clone();
         let running_clone = running.clone();
+        let paused_clone = paused.clone();
         let handle = thread::spawn(move || {
+            // Raw input has no scancodes or text, only the key and the modifiers held
+            let mut keyboard = Keyboard::default();
+            let send = |event| {
+                if let Ok(mut dispatcher) = dispatcher_clone.lock() {
+                    dispatcher.send(event);
+                }
+            };
             let mut manager = RawInputManager::new().unwrap();
             manager.register_devices(DeviceType::Joysticks(XInputInclude::True));
             manager.register_devices(DeviceType::Keyboards);
@@ -92,76 +322,54 @@ pub fn start_input_listener<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Resu
 
             while running_clone.load(Ordering::SeqCst) {
                 if let Some(event) = manager.get_event() {
+                    // Keep draining raw input while paused, just don't record it
+                    if paused_clone.load(Ordering::SeqCst) {
+                        continue;
+                    }
                     let input_event = match event {
-                        RawEvent::KeyboardEvent(_device_id, key, state) => match state {
-                            State::Pressed => Some(InputEvent::new(
-                                "keydown",
-                                serde_json::json!({
-                                    "key": format!("{:?}", key)
-                                }),
-                            )),
-                            State::Released => Some(InputEvent::new(
-                                "keyup",
-                                serde_json::json!({
-                                    "key": format!("{:?}", key)
-                                }),
-                            )),
-                        },
-                        RawEvent::MouseMoveEvent(_device_id, x, y) => Some(InputEvent::new(
-                            "mousedelta",
-                            serde_json::json!({
-                                "x": x,
-                                "y": y
-                            }),
-                        )),
+                        RawEvent::KeyboardEvent(_device_id, key, state) => {
+                            let pressed = matches!(state, State::Pressed);
+                            for event in
+                                keyboard.key_event(format!("{:?}", key), pressed, None, None)
+                            {
+                                send(event);
+                            }
+                            None
+                        }
+                        RawEvent::MouseMoveEvent(_device_id, x, y) => {
+                            Some(InputEvent::Mousedelta { x, y })
+                        }
                         RawEvent::MouseButtonEvent(_device_id, button, state) => {
-                            Some(InputEvent::new(
-                                match state {
-                                    State::Pressed => "mousedown",
-                                    State::Released => "mouseup",
-                                },
-                                serde_json::json!({
-                                    "button": format!("{:?}", button)
-                                }),
+                            let cursor = cursor_clone.lock().ok().and_then(|c| *c);
+                            Some(mouse_button(
+                                format!("{:?}", button),
+                                matches!(state, State::Pressed),
+                                cursor,
                             ))
                         }
-                        RawEvent::MouseWheelEvent(_device_id, delta) => Some(InputEvent::new(
-                            "mousewheel",
-                            serde_json::json!({
-                                "delta": delta
-                            }),
-                        )),
-                        RawEvent::JoystickButtonEvent(device_id, button, state) => {
-                            Some(InputEvent::new(
-                                match state {
-                                    State::Pressed => "joystickdown",
-                                    State::Released => "joystickup",
-                                },
-                                serde_json::json!({
-                                    "id": device_id,
-                                    "button": button
-                                }),
-                            ))
+                        // Raw input only reports the vertical wheel
+                        RawEvent::MouseWheelEvent(_device_id, delta) => {
+                            Some(InputEvent::Mousewheel {
+                                delta_x: 0.0,
+                                delta_y: delta,
+                            })
                         }
-                        RawEvent::JoystickAxisEvent(device_id, axis, value) => {
-                            Some(InputEvent::new(
-                                "joystickaxis",
-                                serde_json::json!({
-                                    "id": device_id,
-                                    "axis": format!("{:?}", axis),
-                                    "value": value
-                                }),
-                            ))
+                        RawEvent::JoystickButtonEvent(id, button, state) => Some(match state {
+                            State::Pressed => InputEvent::Joystickdown { id, button },
+                            State::Released => InputEvent::Joystickup { id, button },
+                        }),
+                        RawEvent::JoystickAxisEvent(id, axis, value) => {
+                            Some(InputEvent::Joystickaxis {
+                                id,
+                                axis: format!("{:?}", axis),
+                                value,
+                            })
                         }
                         _ => None,
                     };
 
                     if let Some(event) = input_event {
-                        if let Err(e) = other_app_handle.emit("input-event", &event) {
-                            error!("Failed to emit input event: {}", e);
-                        }
-                        // Log the input event
-                        let _ = record::log_input(event.to_log_entry());
+                        send(event);
                     }
                 }
             }
@@ -170,23 +378,26 @@ pub fn start_input_listener<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Resu
 
         // For Windows, we also need a separate rdev listener for absolute mouse position
         let running_clone = running.clone();
+        let paused_clone = paused.clone();
         let handle = thread::spawn(move || {
             let callback = move |event: RdevEvent| {
                 if let RdevEventType::MouseMove { x, y } = event.event_type {
-                    let input_event = InputEvent::new(
-                        "mousemove",
-                        serde_json::json!({
-                            "x": x,
-                            "y": y
-                        }),
-                    );
-                    // Log the mouse move event
-                    let _ = record::log_input(input_event.to_log_entry());
+                    if let Ok(mut cursor) = cursor.lock() {
+                        *cursor = Some((x, y));
+                    }
+                    if let Ok(mut dispatcher) = dispatcher.lock() {
+                        dispatcher.send(InputEvent::Mousemove {
+                            x,
+                            y,
+                            screen_x: None,
+                            screen_y: None,
+                        });
+                    }
                 }
             };
 
             if let Err(error) = listen(move |event| {
-                if !running_clone.load(Ordering::SeqCst) {
+                if !running_clone.load(Ordering::SeqCst) || paused_clone.load(Ordering::SeqCst) {
                     return;
                 }
                 callback(event);
@@ -201,62 +412,56 @@ pub fn start_input_listener<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Resu
     #[cfg(not(target_os = "windows"))]
     {
         let running_clone = running.clone();
+        let paused_clone = paused.clone();
         let handle = thread::spawn(move || {
-            let callback = move |event: RdevEvent| {
+            let mut keyboard = Keyboard::default();
+            let mut dispatcher = Dispatcher::new(other_app_handle, &sampling);
+            let mut cursor = None;
+            let mut callback = move |event: RdevEvent| {
+                // Text the key produced with the current layout; dead keys produce none yet
+                let text = event
+                    .unicode
+                    .filter(|unicode| !unicode.is_dead)
+                    .and_then(|unicode| unicode.name);
+                let scancode = Some(event.position_code);
                 let input_event = match event.event_type {
-                    RdevEventType::KeyPress(key) => Some(InputEvent::new(
-                        "keydown",
-                        serde_json::json!({
-                            "key": format!("{:?}", key)
-                        }),
-                    )),
-                    RdevEventType::KeyRelease(key) => Some(InputEvent::new(
-                        "keyup",
-                        serde_json::json!({
-                            "key": format!("{:?}", key)
-                        }),
-                    )),
-                    RdevEventType::ButtonPress(button) => Some(InputEvent::new(
-                        "mousedown",
-                        serde_json::json!({
-                            "button": format!("{:?}", button)
-                        }),
-                    )),
-                    RdevEventType::ButtonRelease(button) => Some(InputEvent::new(
-                        "mouseup",
-                        serde_json::json!({
-                            "button": format!("{:?}", button)
-                        }),
-                    )),
-                    RdevEventType::Wheel {
-                        delta_x: _,
-                        delta_y,
-                    } => Some(InputEvent::new(
-                        "mousewheel",
-                        serde_json::json!({
-                            "delta": delta_y as f32
-                        }),
-                    )),
-                    RdevEventType::MouseMove { x, y } => Some(InputEvent::new(
-                        "mousemove",
-                        serde_json::json!({
-                            "x": x,
-                            "y": y
-                        }),
-                    )),
+                    RdevEventType::KeyPress(key) | RdevEventType::KeyRelease(key) => {
+                        let pressed = matches!(event.event_type, RdevEventType::KeyPress(_));
+                        for event in
+                            keyboard.key_event(format!("{:?}", key), pressed, scancode, text)
+                        {
+                            dispatcher.send(event);
+                        }
+                        None
+                    }
+                    RdevEventType::ButtonPress(button) => {
+                        Some(mouse_button(format!("{:?}", button), true, cursor))
+                    }
+                    RdevEventType::ButtonRelease(button) => {
+                        Some(mouse_button(format!("{:?}", button), false, cursor))
+                    }
+                    RdevEventType::Wheel { delta_x, delta_y } => Some(InputEvent::Mousewheel {
+                        delta_x: delta_x as f32,
+                        delta_y: delta_y as f32,
+                    }),
+                    RdevEventType::MouseMove { x, y } => {
+                        cursor = Some((x, y));
+                        Some(InputEvent::Mousemove {
+                            x,
+                            y,
+                            screen_x: None,
+                            screen_y: None,
+                        })
+                    }
                 };
 
                 if let Some(event) = input_event {
-                    if let Err(e) = other_app_handle.emit("input-event", &event) {
-                        error!("Failed to emit input event: {}", e);
-                    }
-                    // Log the input event
-                    let _ = record::log_input(event.to_log_entry());
+                    dispatcher.send(event);
                 }
             };
 
             if let Err(error) = listen(move |event| {
-                if !running_clone.load(Ordering::SeqCst) {
+                if !running_clone.load(Ordering::SeqCst) || paused_clone.load(Ordering::SeqCst) {
                     return;
                 }
                 callback(event);
@@ -267,16 +472,5 @@ pub fn start_input_listener<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Resu
         input_listener.threads.push(handle);
     }
 
-    *state = Some(input_listener);
-    Ok(())
-}
-
-pub fn stop_input_listener() -> Result<(), String> {
-    info!("[Input] Stopping input listener");
-    let mut state = INPUT_LISTENER_STATE.lock().map_err(|e| e.to_string())?;
-    if let Some(mut listener) = state.take() {
-        listener.stop();
-    }
-    info!("[Input] Input listener stopped");
-    Ok(())
+    Ok(input_listener)
 }
@@ -0,0 +1,474 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Version of the input_log.jsonl format written by this build. Bump it whenever an event
+/// or field changes, and teach `read_input_log` how to read the previous version.
+///
+/// 1: no `schema_version` in the header (or no header at all, in which case entries have
+///    no `offset_ms` either)
+/// 2: typed events, `schema_version` in the `session_start` header
+/// 3: modifiers, scancode and text on key events, `shortcut` events
+/// 4: both scroll axes on `mousewheel`, cursor position on `mousedown`/`mouseup`
+/// 5: `redacted_keys` markers
+/// 6: `private_start`/`private_end` markers around scrubbed private ranges in exports
+pub const SCHEMA_VERSION: u32 = 6;
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// Everything that can appear in input_log.jsonl, tagged by `event` with its payload in
+/// `data`. The same shape is emitted to the frontend as `input-event`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum InputEvent {
+    /// First line of every log
+    SessionStart {
+        #[serde(default = "legacy_schema_version")]
+        schema_version: u32,
+        started_at: String,
+        // Clock `offset_ms` is measured on
+        clock: String,
+    },
+    /// `modifiers` lists the held modifiers ("ctrl", "alt", "altgr", "shift", "meta"),
+    /// including this key when it is one. `text` is what the key typed with the active
+    /// layout. Scancode and text are only captured where the platform reports them.
+    Keydown {
+        key: String,
+        #[serde(default)]
+        modifiers: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scancode: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    Keyup {
+        key: String,
+        #[serde(default)]
+        modifiers: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scancode: Option<u32>,
+    },
+    /// Logged right after the keydown of a key pressed while Ctrl, Alt or Meta is held,
+    /// e.g. `keys` "Ctrl+Shift+T"
+    Shortcut {
+        keys: String,
+        modifiers: Vec<String>,
+        key: String,
+    },
+    /// Stands in for the key events typed while focus was in a password field or a
+    /// denylisted app; logged once per redacted span. `reason` is "secure_field" or
+    /// "denylisted_app:<entry>".
+    RedactedKeys {
+        reason: String,
+    },
+    /// Only in exported logs: the events up to `private_end` fall in a private range and had
+    /// their keys, text and accessibility values replaced with "[redacted]"
+    PrivateStart,
+    /// `redacted` counts the events scrubbed since `private_start`
+    PrivateEnd {
+        redacted: usize,
+    },
+    /// Absolute cursor position. When a window or region is recorded `x`/`y` are relative
+    /// to it and the screen position is kept in `screen_x`/`screen_y`.
+    Mousemove {
+        x: f64,
+        y: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        screen_x: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        screen_y: Option<f64>,
+    },
+    /// Raw relative mouse motion (Windows only)
+    Mousedelta {
+        x: i32,
+        y: i32,
+    },
+    /// Cursor position at the time of the click, made relative like `mousemove`'s. Missing
+    /// when the cursor hadn't moved yet since the listener started.
+    Mousedown {
+        button: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        x: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        y: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        screen_x: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        screen_y: Option<f64>,
+    },
+    Mouseup {
+        button: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        x: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        y: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        screen_x: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        screen_y: Option<f64>,
+    },
+    /// Before version 4 only the vertical axis was logged, as `delta`
+    Mousewheel {
+        #[serde(default)]
+        delta_x: f32,
+        #[serde(alias = "delta")]
+        delta_y: f32,
+    },
+    Joystickdown {
+        id: usize,
+        button: usize,
+    },
+    Joystickup {
+        id: usize,
+        button: usize,
+    },
+    Joystickaxis {
+        id: usize,
+        axis: String,
+        value: f64,
+    },
+    /// Accessibility tree snapshot, as produced by the dump-tree tool
+    Axtree(serde_json::Value),
+    FfmpegStdout {
+        output: String,
+    },
+    FfmpegStderr {
+        output: String,
+    },
+}
+
+impl InputEvent {
+    /// Cursor position carried by the event
+    pub fn position(&self) -> Option<(f64, f64)> {
+        match *self {
+            InputEvent::Mousemove { x, y, .. } => Some((x, y)),
+            InputEvent::Mousedown {
+                x: Some(x),
+                y: Some(y),
+                ..
+            }
+            | InputEvent::Mouseup {
+                x: Some(x),
+                y: Some(y),
+                ..
+            } => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    /// Makes the position relative to `origin`, keeping the original one in
+    /// `screen_x`/`screen_y`
+    pub fn make_relative(&mut self, origin: (f64, f64)) {
+        let Some((screen_x, screen_y)) = self.position() else {
+            return;
+        };
+        let (x, y) = (screen_x - origin.0, screen_y - origin.1);
+        match self {
+            InputEvent::Mousemove {
+                x: event_x,
+                y: event_y,
+                screen_x: event_screen_x,
+                screen_y: event_screen_y,
+            } => {
+                (*event_x, *event_y) = (x, y);
+                (*event_screen_x, *event_screen_y) = (Some(screen_x), Some(screen_y));
+            }
+            InputEvent::Mousedown {
+                x: event_x,
+                y: event_y,
+                screen_x: event_screen_x,
+                screen_y: event_screen_y,
+                ..
+            }
+            | InputEvent::Mouseup {
+                x: event_x,
+                y: event_y,
+                screen_x: event_screen_x,
+                screen_y: event_screen_y,
+                ..
+            } => {
+                (*event_x, *event_y) = (Some(x), Some(y));
+                (*event_screen_x, *event_screen_y) = (Some(screen_x), Some(screen_y));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One line of input_log.jsonl
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct LogEntry {
+    #[serde(flatten)]
+    pub event: InputEvent,
+    /// Wall-clock time, in milliseconds since the Unix epoch
+    pub time: i64,
+    /// Position on the video timeline, in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset_ms: Option<i64>,
+    /// Index into the recorded monitors listed in meta.json
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<usize>,
+}
+
+impl LogEntry {
+    pub fn new(event: InputEvent) -> Self {
+        Self {
+            event,
+            time: chrono::Local::now().timestamp_millis(),
+            offset_ms: None,
+            monitor: None,
+        }
+    }
+}
+
+/// A parsed input log, upgraded to the current schema
+pub struct InputLog {
+    /// Version the file was written with
+    pub schema_version: u32,
+    pub entries: Vec<LogEntry>,
+}
+
+/// JSON Schema describing a single line of input_log.jsonl
+pub fn input_log_schema() -> serde_json::Value {
+    let mut schema = serde_json::to_value(schemars::schema_for!(LogEntry))
+        .expect("input log schema is serializable");
+    schema["title"] = serde_json::json!("input_log.jsonl entry");
+    schema["schema_version"] = serde_json::json!(SCHEMA_VERSION);
+    schema
+}
+
+/// Reads an input_log.jsonl written with any schema version up to `SCHEMA_VERSION`.
+/// Lines that can't be parsed are skipped with a warning.
+pub fn read_input_log(path: &Path) -> Result<InputLog, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open input log: {}", e))?;
+
+    let mut lines = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(&line) {
+            Ok(value) => lines.push((index, value)),
+            Err(e) => log::warn!(
+                "[InputLog] Skipping line {} of {}: {}",
+                index + 1,
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    let header = lines
+        .first()
+        .map(|(_, line)| line)
+        .filter(|line| line["event"] == "session_start");
+    let schema_version = header
+        .and_then(|header| header["data"]["schema_version"].as_u64())
+        .map_or(legacy_schema_version(), |version| version as u32);
+    if schema_version > SCHEMA_VERSION {
+        return Err(format!(
+            "Input log uses schema version {}, this version only reads up to {}",
+            schema_version, SCHEMA_VERSION
+        ));
+    }
+
+    if schema_version == 1 {
+        upgrade_from_v1(&mut lines);
+    }
+
+    let mut entries = Vec::new();
+    for (index, line) in lines {
+        match serde_json::from_value::<LogEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => log::warn!(
+                "[InputLog] Skipping line {} of {}: {}",
+                index + 1,
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    // Version 1 logs without a header only have the wall-clock `time`, taken relative to
+    // the first line
+    if let Some(reference) = entries.first().map(|entry| entry.time) {
+        for entry in entries.iter_mut().filter(|entry| entry.offset_ms.is_none()) {
+            entry.offset_ms = Some(entry.time - reference);
+        }
+    }
+
+    Ok(InputLog {
+        schema_version,
+        entries,
+    })
+}
+
+// Version 1 lines are whatever JSON the capture code built. Input and ffmpeg events already
+// have the `event`/`data`/`time` shape, but accessibility snapshots were logged flat, as
+// `{"event": "axtree", <tree fields>...}`, with neither `data` nor `time`. Their fields are
+// moved into `data` and they get the time of the line before them (or of the first timed
+// line, at the start of the log).
+fn upgrade_from_v1(lines: &mut [(usize, Value)]) {
+    let mut previous_time = lines.iter().find_map(|(_, line)| line["time"].as_i64());
+
+    for (_, line) in lines {
+        let Some(fields) = line.as_object_mut() else {
+            continue;
+        };
+
+        if fields.get("event").and_then(Value::as_str) == Some("axtree")
+            && !fields.contains_key("data")
+        {
+            let mut tree = std::mem::take(fields);
+            for key in ["event", "time", "offset_ms", "monitor"] {
+                if let Some(value) = tree.remove(key) {
+                    fields.insert(key.to_string(), value);
+                }
+            }
+            fields.insert("data".to_string(), Value::Object(tree));
+        }
+
+        match fields.get("time").and_then(Value::as_i64) {
+            Some(time) => previous_time = Some(time),
+            None => {
+                if let Some(time) = previous_time {
+                    fields.insert("time".to_string(), time.into());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    fn event_names(log: &InputLog) -> Vec<String> {
+        log.entries
+            .iter()
+            .map(|entry| serde_json::to_value(&entry.event).unwrap())
+            .map(|event| event["event"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reads_headerless_v1_logs() {
+        let log = read_input_log(&fixture("input_log_v1.jsonl")).unwrap();
+
+        assert_eq!(log.schema_version, 1);
+        // The truncated last line is skipped
+        assert_eq!(
+            event_names(&log),
+            [
+                "axtree",
+                "mousemove",
+                "keydown",
+                "keyup",
+                "mousedown",
+                "mouseup",
+                "mousewheel",
+                "axtree",
+                "ffmpeg_stderr",
+            ]
+        );
+
+        let offsets: Vec<i64> = log.entries.iter().map(|e| e.offset_ms.unwrap()).collect();
+        assert_eq!(offsets, [0, 0, 100, 150, 200, 250, 300, 300, 400]);
+
+        match &log.entries[6].event {
+            InputEvent::Mousewheel { delta_x, delta_y } => {
+                assert_eq!((*delta_x, *delta_y), (0.0, -1.0))
+            }
+            event => panic!("expected mousewheel, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn moves_flat_v1_snapshots_into_data() {
+        let log = read_input_log(&fixture("input_log_v1.jsonl")).unwrap();
+
+        let InputEvent::Axtree(snapshot) = &log.entries[7].event else {
+            panic!("expected axtree, got {:?}", log.entries[7].event);
+        };
+        assert_eq!(snapshot["tree"][0]["name"], "Firefox");
+        assert_eq!(snapshot["platform"], "linux");
+        assert!(snapshot.get("event").is_none());
+        // Snapshots had no time of their own
+        assert_eq!(log.entries[7].time, 1700000000400);
+        assert_eq!(log.entries[0].time, 1700000000100);
+    }
+
+    #[test]
+    fn reads_v1_logs_with_a_header() {
+        let log = read_input_log(&fixture("input_log_v1_header.jsonl")).unwrap();
+
+        assert_eq!(log.schema_version, 1);
+        assert_eq!(
+            event_names(&log),
+            ["session_start", "mousemove", "axtree", "keydown"]
+        );
+
+        let snapshot = &log.entries[2];
+        assert_eq!(snapshot.offset_ms, Some(2000));
+        assert_eq!(snapshot.time, 1735725600120);
+        let InputEvent::Axtree(tree) = &snapshot.event else {
+            panic!("expected axtree, got {:?}", snapshot.event);
+        };
+        assert_eq!(tree["tree"][0]["name"], "Files");
+        assert!(tree.get("offset_ms").is_none());
+        assert_eq!(log.entries[1].monitor, Some(1));
+    }
+
+    #[test]
+    fn reads_current_logs() {
+        let log = read_input_log(&fixture("input_log_v6.jsonl")).unwrap();
+
+        assert_eq!(log.schema_version, SCHEMA_VERSION);
+        assert_eq!(
+            event_names(&log),
+            [
+                "session_start",
+                "keydown",
+                "shortcut",
+                "redacted_keys",
+                "mousewheel",
+                "axtree",
+            ]
+        );
+        match &log.entries[1].event {
+            InputEvent::Keydown {
+                key,
+                modifiers,
+                scancode,
+                text,
+            } => {
+                assert_eq!(key, "KeyA");
+                assert_eq!(modifiers, &["shift"]);
+                assert_eq!(*scancode, Some(30));
+                assert_eq!(text.as_deref(), Some("A"));
+            }
+            event => panic!("expected keydown, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn rejects_newer_schema_versions() {
+        let error = read_input_log(&fixture("input_log_future.jsonl"))
+            .err()
+            .unwrap();
+        assert!(error.contains("schema version 99"), "{}", error);
+    }
+}
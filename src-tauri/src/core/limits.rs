@@ -0,0 +1,115 @@
+use crate::utils::settings::RecordingLimits;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use sysinfo::Disks;
+use tauri::Emitter;
+
+const MB: u64 = 1024 * 1024;
+
+/// Free bytes on the disk holding `path`, or `None` when no mounted disk contains it
+pub fn available_space(path: &Path) -> Option<u64> {
+    let disks = Disks::new_with_refreshed_list();
+    // Nested mounts (e.g. /home on its own partition) win over the root filesystem
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// Refuses to start a recording when the recordings disk is already below the minimum
+pub fn check_free_space(recordings_dir: &Path, limits: &RecordingLimits) -> Result<(), String> {
+    let Some(available) = available_space(recordings_dir) else {
+        log::warn!(
+            "[Limits] Could not determine free space for {}",
+            recordings_dir.display()
+        );
+        return Ok(());
+    };
+
+    if available < limits.min_free_space_mb * MB {
+        return Err(format!(
+            "Not enough disk space to record: {} MB free, at least {} MB required",
+            available / MB,
+            limits.min_free_space_mb
+        ));
+    }
+    Ok(())
+}
+
+/// Total size of the files below `path`
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Checks a running session against the configured limits
+pub struct LimitMonitor {
+    limits: RecordingLimits,
+    session_dir: PathBuf,
+    warned: bool,
+}
+
+impl LimitMonitor {
+    pub fn new(limits: RecordingLimits, session_dir: PathBuf) -> Self {
+        Self {
+            limits,
+            session_dir,
+            warned: false,
+        }
+    }
+
+    /// Returns the stop reason once a limit is exceeded; `elapsed` excludes pauses.
+    /// Emits `recording-disk-warning` the first time free space gets low.
+    pub fn check(&mut self, app: &tauri::AppHandle, elapsed: Duration) -> Option<&'static str> {
+        if let Some(available) = available_space(&self.session_dir) {
+            if available < self.limits.min_free_space_mb * MB {
+                log::warn!("[Limits] Only {} MB free, stopping", available / MB);
+                return Some("disk_limit");
+            }
+
+            if !self.warned && available < self.limits.warn_free_space_mb * MB {
+                self.warned = true;
+                log::warn!(
+                    "[Limits] Disk space is running low: {} MB free",
+                    available / MB
+                );
+                let _ = app.emit(
+                    "recording-disk-warning",
+                    serde_json::json!({
+                        "available_mb": available / MB,
+                        "min_free_space_mb": self.limits.min_free_space_mb
+                    }),
+                );
+            }
+        }
+
+        if let Some(max_minutes) = self.limits.max_duration_minutes {
+            if elapsed >= Duration::from_secs(max_minutes * 60) {
+                log::info!("[Limits] Reached the {} minute limit", max_minutes);
+                return Some("max_duration");
+            }
+        }
+
+        if let Some(max_size_mb) = self.limits.max_size_mb {
+            let size = dir_size(&self.session_dir);
+            if size >= max_size_mb * MB {
+                log::info!("[Limits] Session reached {} MB", size / MB);
+                return Some("max_size");
+            }
+        }
+
+        None
+    }
+}
@@ -1,4 +1,11 @@
 // Re-export all core modules
 pub mod input;
+pub mod input_log;
+pub mod capture;
 pub mod record;
 pub mod archive;
+pub mod recovery;
+pub mod limits;
+pub mod retention;
+pub mod redaction;
+pub mod pii;
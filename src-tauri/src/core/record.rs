@@ -1,21 +1,36 @@
-use crate::core::input;
+use crate::core::archive;
+use crate::core::capture::{self, CaptureRect, CaptureTarget, ResolvedTarget};
+use crate::core::input::{self, InputListener};
+use crate::core::input_log::{self, InputEvent, LogEntry};
+use crate::core::limits::{self, LimitMonitor};
+use crate::core::redaction::{self, PrivateRegion, RegionStyle};
+use crate::core::retention;
+use crate::tools::audio::{self, AudioCapture, AudioInput};
 use crate::tools::axtree;
-use crate::tools::ffmpeg::{init_ffmpeg, FFmpegRecorder, FFMPEG_PATH, FFPROBE_PATH};
+use crate::tools::ffmpeg::{
+    init_ffmpeg, probe_capabilities, CaptureOptions, FFmpegProgress, FFmpegRecorder, FFMPEG_PATH,
+    FFPROBE_PATH,
+};
 use crate::tools::pipeline;
+#[cfg(target_os = "linux")]
+use crate::tools::wayland::{self, WaylandRecorder};
 use crate::utils::logger::Logger;
+use crate::utils::settings::{EncodingProfile, PrivateEventMode, RecordingLimits, Settings};
+use crate::utils::storage;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::Local;
 use display_info::DisplayInfo;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fs::{self, create_dir_all, File};
-use std::io::{BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use tauri::{Emitter, Manager, State};
 use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_opener::OpenerExt;
-use zip::{write::FileOptions, ZipWriter};
+use zip::ZipWriter;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RecordingMeta {
@@ -32,6 +47,75 @@ pub struct RecordingMeta {
     locale: String,
     monitor: MonitorInfo,
     quest: Option<Quest>,
+    #[serde(default)]
+    pauses: Vec<PauseSegment>,
+    // Every recorded monitor; `monitor` stays the first one for older readers
+    #[serde(default)]
+    monitors: Vec<RecordedMonitor>,
+    // Set when a window or region was recorded instead of whole monitors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    capture: Option<CaptureInfo>,
+    // Audio sources muxed into the first monitor's video
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audio: Option<AudioCapture>,
+    // Protects the recording from the retention policy
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uploaded_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CaptureInfo {
+    target: CaptureTarget,
+    // Area when the recording started; a tracked window may move afterwards
+    area: CaptureRect,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecordedMonitor {
+    // Referenced by the "monitor" field of events in input_log.jsonl
+    index: usize,
+    video: String,
+    #[serde(flatten)]
+    monitor: MonitorInfo,
+}
+
+impl RecordingMeta {
+    // Which monitor `video` shows and the size of the area it recorded, before the encoding
+    // profile's downscaling
+    fn recorded_area(&self, video: &str) -> Option<RecordedArea> {
+        let (index, monitor) = match self.monitors.iter().find(|m| m.video == video) {
+            Some(recorded) => (recorded.index, &recorded.monitor),
+            // Sessions from before multi-monitor recording only have recording.mp4
+            None if video == "recording.mp4" => (0, &self.monitor),
+            None => return None,
+        };
+        let (width, height) = match &self.capture {
+            Some(capture) => (capture.area.width, capture.area.height),
+            None => (monitor.width, monitor.height),
+        };
+        Some(RecordedArea {
+            monitor: index,
+            width,
+            height,
+        })
+    }
+}
+
+struct RecordedArea {
+    monitor: usize,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PauseSegment {
+    paused_at: String,
+    resumed_at: String,
+    // Where the cut sits in recording.mp4 (and in input_log.jsonl time)
+    video_offset_ms: i64,
+    duration_ms: i64,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -63,9 +147,33 @@ pub struct MonitorInfo {
     y: i32
 }
 
+impl MonitorInfo {
+    fn from_display(display: &DisplayInfo) -> Self {
+        Self {
+            width: display.width,
+            height: display.height,
+            x: display.x,
+            y: display.y,
+        }
+    }
+
+    pub fn origin(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x as f64
+            && y >= self.y as f64
+            && x < self.x as f64 + self.width as f64
+            && y < self.y as f64 + self.height as f64
+    }
+}
+
 enum Recorder {
     // #[cfg(not(target_os = "macos"))]
     FFmpeg(FFmpegRecorder),
+    #[cfg(target_os = "linux")]
+    Wayland(WaylandRecorder),
     // #[cfg(target_os = "macos")]
     // MacOS(MacOSScreenRecorder),
 }
@@ -75,6 +183,8 @@ impl Recorder {
         match self {
             // #[cfg(not(target_os = "macos"))]
             Recorder::FFmpeg(recorder) => recorder.start(),
+            #[cfg(target_os = "linux")]
+            Recorder::Wayland(recorder) => recorder.start(),
             // #[cfg(target_os = "macos")]
             // Recorder::MacOS(recorder) => recorder.start(),
         }
@@ -84,12 +194,49 @@ impl Recorder {
         match self {
             // #[cfg(not(target_os = "macos"))]
             Recorder::FFmpeg(recorder) => recorder.stop(),
+            #[cfg(target_os = "linux")]
+            Recorder::Wayland(recorder) => recorder.stop(),
             // #[cfg(target_os = "macos")]
             // Recorder::MacOS(recorder) => recorder.stop(),
         }
     }
 
-    fn new(video_path: &PathBuf, display: &DisplayInfo) -> Result<Self, String> {
+    fn pause(&mut self) -> Result<(), String> {
+        match self {
+            Recorder::FFmpeg(recorder) => recorder.pause(),
+            #[cfg(target_os = "linux")]
+            Recorder::Wayland(recorder) => recorder.pause(),
+        }
+    }
+
+    // Both backends write a fresh segment when started again after a pause
+    fn resume(&mut self) -> Result<(), String> {
+        self.start()
+    }
+
+    fn first_frame_at(&self) -> Option<Instant> {
+        match self {
+            Recorder::FFmpeg(recorder) => recorder.first_frame_at(),
+            #[cfg(target_os = "linux")]
+            Recorder::Wayland(recorder) => recorder.first_frame_at(),
+        }
+    }
+
+    fn health(&mut self) -> Result<FFmpegProgress, String> {
+        match self {
+            Recorder::FFmpeg(recorder) => recorder.health(),
+            #[cfg(target_os = "linux")]
+            Recorder::Wayland(recorder) => recorder.health(),
+        }
+    }
+
+    fn new(
+        video_path: &PathBuf,
+        display: &DisplayInfo,
+        target: Option<&ResolvedTarget>,
+        encoding: &EncodingProfile,
+        audio: Vec<AudioInput>,
+    ) -> Result<Self, String> {
         log::info!("[record] Starting new recorder");
         // #[cfg(target_os = "macos")]
         // {
@@ -99,6 +246,29 @@ impl Recorder {
         //     )));
         // }
 
+        // Fail here with a clear message rather than spawning an ffmpeg that exits immediately
+        let capabilities = probe_capabilities()?;
+        let encoding = &capabilities.select_encoding(encoding)?;
+
+        // x11grab only sees XWayland surfaces, so Wayland sessions go through the portal
+        #[cfg(target_os = "linux")]
+        if wayland::is_wayland_session() {
+            log::info!("[record] Wayland session detected, using the ScreenCast portal");
+            if !capabilities.has_capture_input("pipewire") {
+                return Err(
+                    "Screen capture on Wayland needs GStreamer with the PipeWire plugin \
+                     (gst-launch-1.0 and pipewiresrc)"
+                        .to_string(),
+                );
+            }
+            return Ok(Recorder::Wayland(WaylandRecorder::new(
+                encoding.clone(),
+                video_path.to_path_buf(),
+                target.and_then(|t| t.window_id()).is_some(),
+            )
+            .with_audio(audio)));
+        }
+
         // #[cfg(not(target_os = "macos"))]
         {
             let (input_format, input_device) = {
@@ -108,7 +278,7 @@ impl Recorder {
                 }
                 #[cfg(target_os = "linux")]
                 {
-                    ("x11grab", ":0.0".to_string())
+                    ("x11grab", x11grab_display())
                 }
                 #[cfg(target_os = "macos")]
                 {
@@ -184,41 +354,607 @@ impl Recorder {
                 }
             };
 
-            Ok(Recorder::FFmpeg(FFmpegRecorder::new_with_input(
-                display.x,
-                display.y,
-                display.width,
-                display.height,
-                30,
-                video_path.to_path_buf(),
-                input_format.to_string(),
+            if !capabilities.has_capture_input(input_format) {
+                return Err(format!(
+                    "The installed FFmpeg build ({}) does not support {} screen capture",
+                    capabilities.path, input_format
+                ));
+            }
+
+            let (area, input_device, capture_options) = match target {
+                Some(target) => target_input(target, display, input_format, input_device)?,
+                None => (
+                    CaptureRect {
+                        x: display.x,
+                        y: display.y,
+                        width: display.width,
+                        height: display.height,
+                    },
+                    input_device,
+                    CaptureOptions::default(),
+                ),
+            };
+
+            // x11grab takes the capture offset as part of the device name, e.g. `:1.0+1920,0`
+            let input_device = if input_format == "x11grab" {
+                format!("{}+{},{}", input_device, area.x.max(0), area.y.max(0))
+            } else {
+                input_device
+            };
+
+            Ok(Recorder::FFmpeg(
+                FFmpegRecorder::new_with_input(
+                    area.x,
+                    area.y,
+                    area.width,
+                    area.height,
+                    encoding.clone(),
+                    video_path.to_path_buf(),
+                    input_format.to_string(),
+                    input_device,
+                )
+                .with_capture_options(capture_options)
+                .with_audio(audio),
+            ))
+        }
+    }
+}
+
+/// Returns the X display to grab from `$DISPLAY` (Xvfb, remote or non-zero displays),
+/// e.g. `:1.0`
+#[cfg(target_os = "linux")]
+fn x11grab_display() -> String {
+    let x_display = std::env::var("DISPLAY")
+        .ok()
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| ":0".to_string());
+
+    // x11grab wants host:display.screen, DISPLAY often leaves out the screen number
+    let screen_given = x_display
+        .rsplit_once(':')
+        .map(|(_, number)| number.contains('.'))
+        .unwrap_or(false);
+    let x_display = if screen_given {
+        x_display
+    } else {
+        format!("{}.0", x_display)
+    };
+
+    log::info!("[record] Using X11 display {}", x_display);
+    x_display
+}
+
+// Maps a window or region target onto the capture device. Returns the area handed to
+// ffmpeg (relative to the window for window captures), the device and extra input options.
+fn target_input(
+    target: &ResolvedTarget,
+    display: &DisplayInfo,
+    input_format: &str,
+    input_device: String,
+) -> Result<(CaptureRect, String, CaptureOptions), String> {
+    let area = target.area;
+    let window_area = CaptureRect { x: 0, y: 0, ..area };
+
+    match (input_format, target.window_id()) {
+        // Both grabbers follow the window by themselves when it moves
+        ("x11grab", Some(id)) => Ok((
+            window_area,
+            input_device,
+            CaptureOptions {
+                input_args: vec!["-window_id".to_string(), format!("0x{:x}", id)],
+                ..Default::default()
+            },
+        )),
+        ("gdigrab", Some(_)) => {
+            let title = target
+                .title
+                .clone()
+                .filter(|title| !title.is_empty())
+                .ok_or_else(|| "The selected window has no title to capture it by".to_string())?;
+            Ok((
+                window_area,
+                format!("title={}", title),
+                CaptureOptions {
+                    filter: Some("crop=trunc(iw/2)*2:trunc(ih/2)*2".to_string()),
+                    auto_size: true,
+                    ..Default::default()
+                },
+            ))
+        }
+        // avfoundation only grabs whole screens, so crop the target out of the frame. The
+        // crop is fixed: a window moved during the recording is not followed.
+        ("avfoundation", _) => {
+            if target.window_id().is_some() {
+                log::warn!("[record] avfoundation cannot follow windows, using its start position");
+            }
+            // Relative to the frame size since Retina screens capture at twice the logical size
+            let (dw, dh) = (display.width.max(1), display.height.max(1));
+            let x = (area.x - display.x).max(0);
+            let y = (area.y - display.y).max(0);
+            let filter = format!(
+                "crop=trunc(iw*{w}/{dw}/2)*2:trunc(ih*{h}/{dh}/2)*2:iw*{x}/{dw}:ih*{y}/{dh}",
+                w = area.width.min(display.width),
+                h = area.height.min(display.height),
+            );
+            Ok((
+                CaptureRect {
+                    x: display.x,
+                    y: display.y,
+                    width: display.width,
+                    height: display.height,
+                },
                 input_device,
-            )))
+                CaptureOptions {
+                    filter: Some(filter),
+                    ..Default::default()
+                },
+            ))
+        }
+        (_, None) => Ok((area, input_device, CaptureOptions::default())),
+        (_, Some(_)) => Err(format!("Window capture is not supported with {}", input_format)),
+    }
+}
+
+// Picks the monitor a window or region mostly sits on, judged by its center
+fn target_display<'a>(
+    all_displays: &'a [DisplayInfo],
+    area: &CaptureRect,
+) -> Result<&'a DisplayInfo, String> {
+    let center_x = area.x as f64 + area.width as f64 / 2.0;
+    let center_y = area.y as f64 + area.height as f64 / 2.0;
+    all_displays
+        .iter()
+        .find(|d| MonitorInfo::from_display(d).contains(center_x, center_y))
+        .or_else(|| all_displays.iter().find(|d| d.is_primary))
+        .or_else(|| all_displays.first())
+        .ok_or_else(|| "No displays found".to_string())
+}
+
+// Resolves the requested monitors against the connected displays. `displays` selects several
+// monitors; otherwise `display` (or the primary monitor) is recorded on its own.
+fn select_displays<'a>(
+    all_displays: &'a [DisplayInfo],
+    display: Option<MonitorInfo>,
+    displays: Option<Vec<MonitorInfo>>,
+) -> Result<Vec<&'a DisplayInfo>, String> {
+    if let Some(selected) = displays.filter(|d| !d.is_empty()) {
+        let mut found: Vec<&DisplayInfo> = Vec::new();
+        for m in &selected {
+            match all_displays.iter().find(|d| d.x == m.x && d.y == m.y) {
+                Some(d) if !found.iter().any(|f| f.id == d.id) => found.push(d),
+                Some(_) => {}
+                None => log::warn!(
+                    "[record] Selected monitor at ({}, {}) not found, skipping",
+                    m.x,
+                    m.y
+                ),
+            }
+        }
+        if found.is_empty() {
+            return Err("None of the selected monitors were found".to_string());
         }
+        return Ok(found);
+    }
+
+    let recorded_display = match display
+    {
+        Some(m) => {
+            all_displays
+                .iter()
+                .find(|d| d.x == m.x && d.y == m.y)
+                .or_else(|| all_displays.first())
+                .ok_or_else(|| "No displays found".to_string())?
+        },
+        None => {
+            all_displays
+                .iter()
+                .find(|d| d.is_primary)
+                .or_else(|| all_displays.first())
+                .ok_or_else(|| "No displays found".to_string())?
+        }
+    };
+    Ok(vec![recorded_display])
+}
+
+// Logs mouse positions relative to the captured area, following the window where the
+// grabber does too (x11grab and gdigrab)
+fn start_target_tracking(target: &ResolvedTarget, logger: &Logger) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    if wayland::is_wayland_session() {
+        log::warn!("[record] Window position is unknown on Wayland, logging screen coordinates");
+        return Ok(());
+    }
+
+    let area = Arc::new(Mutex::new(target.area));
+    #[cfg(not(target_os = "macos"))]
+    if let Some(id) = target.window_id() {
+        capture::start_window_tracker(id, area.clone())?;
+    }
+    logger.set_capture_area(area)
+}
+
+// The first monitor keeps the historical file name so single-monitor sessions are unchanged
+fn monitor_video_name(index: usize) -> String {
+    if index == 0 {
+        "recording.mp4".to_string()
+    } else {
+        format!("recording_{}.mp4", index)
+    }
+}
+
+// Runs the same action on every monitor's recorder at once so their videos stay in step.
+// All recorders get the action even when one fails; the first error is returned.
+fn for_each_recorder(
+    recorders: &mut [Recorder],
+    action: fn(&mut Recorder) -> Result<(), String>,
+) -> Result<(), String> {
+    if let [recorder] = recorders {
+        return action(recorder);
     }
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = recorders
+            .iter_mut()
+            .map(|recorder| scope.spawn(move || action(recorder)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Recorder thread panicked".to_string()))
+            })
+            .fold(Ok(()), |result, next| result.and(next))
+    })
 }
 
 #[derive(Default)]
 pub struct QuestState {
-    pub recording_start_time: Mutex<Option<chrono::DateTime<chrono::Local>>>,
-    pub current_recording_id: Mutex<Option<String>>,
     pub current_quest: Mutex<Option<Quest>>,
 }
 
-// Global state for recording and logging and overlay
+#[derive(Default)]
+struct PauseClock {
+    paused_at: Option<chrono::DateTime<Local>>,
+    // Total length of the finished pauses
+    paused_ms: i64,
+}
+
+/// Everything that belongs to the recording in progress. Stop, pause and the health
+/// watchdog work on this object instead of looking the session up on disk.
+pub struct RecordingSession {
+    id: String,
+    dir: PathBuf,
+    started_at: chrono::DateTime<Local>,
+    clock: Mutex<PauseClock>,
+    recorders: Mutex<Vec<Recorder>>,
+    // Not behind a lock: capture threads only hand events to the logger's writer thread
+    logger: Logger,
+    listener: Mutex<Option<InputListener>>,
+}
+
+impl RecordingSession {
+    // Reads this session's meta.json, applies `update` and writes it back
+    fn update_meta(&self, update: impl FnOnce(&mut RecordingMeta)) -> Result<(), String> {
+        let meta_path = self.dir.join("meta.json");
+        let meta_str = fs::read_to_string(&meta_path)
+            .map_err(|e| format!("Failed to read meta file: {}", e))?;
+        let mut meta: RecordingMeta = serde_json::from_str(&meta_str)
+            .map_err(|e| format!("Failed to parse meta file: {}", e))?;
+
+        update(&mut meta);
+
+        fs::write(
+            &meta_path,
+            serde_json::to_string_pretty(&meta)
+                .map_err(|e| format!("Failed to serialize meta: {}", e))?,
+        )
+        .map_err(|e| format!("Failed to write meta file: {}", e))
+    }
+
+    // Recording time so far, not counting pauses (including one in progress)
+    fn active_time(&self) -> Duration {
+        let clock = self.clock.lock().unwrap();
+        let now = Local::now();
+        let paused_ms = clock.paused_ms
+            + clock
+                .paused_at
+                .map(|paused_at| now.signed_duration_since(paused_at).num_milliseconds())
+                .unwrap_or(0);
+        let active_ms = now.signed_duration_since(self.started_at).num_milliseconds() - paused_ms;
+        Duration::from_millis(active_ms.max(0) as u64)
+    }
+
+    fn health(&self) -> Result<Vec<FFmpegProgress>, String> {
+        let mut recorders = self.recorders.lock().map_err(|e| e.to_string())?;
+        recorders.iter_mut().map(Recorder::health).collect()
+    }
+
+    // Anchors input_log.jsonl to the first frame of the current segment once ffmpeg
+    // reports it; the first monitor's video is the reference
+    fn sync_video_origin(&self) -> Result<(), String> {
+        let first_frame_at = self
+            .recorders
+            .lock()
+            .map_err(|e| e.to_string())?
+            .first()
+            .and_then(Recorder::first_frame_at);
+        if let Some(origin) = first_frame_at {
+            self.logger.set_video_origin(origin)?;
+        }
+        Ok(())
+    }
+
+    fn log_input(&self, event: InputEvent) -> Result<(), String> {
+        self.logger.log_input_event(event)
+    }
+
+    fn log_ffmpeg(&self, output: &str, is_stderr: bool) -> Result<(), String> {
+        self.logger.log_ffmpeg(output, is_stderr)
+    }
+
+    fn set_input_paused(&self, paused: bool) -> Result<(), String> {
+        let listener = self.listener.lock().map_err(|e| e.to_string())?;
+        if let Some(listener) = listener.as_ref() {
+            listener.set_paused(paused);
+        }
+        Ok(())
+    }
+
+    // Resumes input capture and dump-tree polling, which `pause` suspends first
+    fn resume_input(&self) -> Result<(), String> {
+        axtree::resume_dump_tree_polling()?;
+        self.set_input_paused(false)
+    }
+
+    fn pause(&self) -> Result<(), String> {
+        self.pause_with(Recorder::pause)
+    }
+
+    // Pausing a recorder ends its process even when the segment fails to finalize, so once
+    // the recorders were asked to pause the session counts as paused and `resume` can start
+    // them again. A failure before that leaves the recording running as it was.
+    fn pause_with(
+        &self,
+        pause_recorder: fn(&mut Recorder) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut clock = self.clock.lock().map_err(|e| e.to_string())?;
+        if clock.paused_at.is_some() {
+            return Err("Recording is already paused".to_string());
+        }
+
+        // Suspend input capture first so nothing is logged past the end of the segment
+        self.set_input_paused(true)?;
+        let suspended = axtree::pause_dump_tree_polling()
+            .and_then(|_| self.sync_video_origin())
+            .and_then(|_| self.logger.end_segment());
+        if let Err(e) = suspended {
+            if let Err(e) = self.resume_input() {
+                log::error!("[record] Failed to resume input after pausing: {}", e);
+            }
+            return Err(e);
+        }
+
+        let mut recorders = self.recorders.lock().map_err(|e| e.to_string())?;
+        let paused = for_each_recorder(&mut recorders, pause_recorder);
+        clock.paused_at = Some(Local::now());
+        paused
+    }
+
+    fn resume(&self) -> Result<(), String> {
+        self.resume_with(Recorder::resume)
+    }
+
+    // The recorders restart first; if one fails, the ones that did start are paused again so
+    // the session stays paused as a whole and resuming can be retried
+    fn resume_with(
+        &self,
+        resume_recorder: fn(&mut Recorder) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut clock = self.clock.lock().map_err(|e| e.to_string())?;
+        let paused_at = clock
+            .paused_at
+            .ok_or_else(|| "Recording is not paused".to_string())?;
+
+        // Holding the recorders keeps the watchdog from reading the old segment's first frame
+        let mut recorders = self.recorders.lock().map_err(|e| e.to_string())?;
+        if let Err(e) = for_each_recorder(&mut recorders, resume_recorder) {
+            if let Err(e) = for_each_recorder(&mut recorders, Recorder::pause) {
+                log::error!("[record] Failed to pause recorders again: {}", e);
+            }
+            return Err(e);
+        }
+        clock.paused_at = None;
+
+        // The video runs again, so everything else has to follow even if one step fails
+        let mut result = Ok(());
+        let recorded = self.record_pause(&mut clock, paused_at);
+        keep_first_error(&mut result, "record the pause", recorded);
+        let started = self.logger.start_segment();
+        keep_first_error(&mut result, "start a log segment", started);
+        drop(recorders);
+        keep_first_error(&mut result, "resume input capture", self.resume_input());
+        result
+    }
+
+    // Adds a finished pause to meta.json and shifts later log timestamps back by its
+    // length, so input_log.jsonl lines up with the concatenated recording.mp4
+    fn record_pause(
+        &self,
+        clock: &mut PauseClock,
+        paused_at: chrono::DateTime<Local>,
+    ) -> Result<(), String> {
+        let resumed_at = Local::now();
+        let duration_ms = resumed_at
+            .signed_duration_since(paused_at)
+            .num_milliseconds()
+            .max(0);
+
+        let video_offset_ms =
+            paused_at.signed_duration_since(self.started_at).num_milliseconds() - clock.paused_ms;
+        clock.paused_ms += duration_ms;
+
+        self.logger.set_time_offset(clock.paused_ms)?;
+
+        self.update_meta(|meta| {
+            meta.pauses.push(PauseSegment {
+                paused_at: paused_at.to_rfc3339(),
+                resumed_at: resumed_at.to_rfc3339(),
+                video_offset_ms,
+                duration_ms,
+            })
+        })
+    }
+
+    // Starts the recorders, the input listener and dump-tree polling. Whatever already runs
+    // when a step fails is left for `stop_captures` to stop.
+    fn start_captures(
+        &self,
+        app: &tauri::AppHandle,
+        settings: &Settings,
+        target: Option<&ResolvedTarget>,
+        displays: &[&DisplayInfo],
+        monitors: &[RecordedMonitor],
+        audio_inputs: Vec<AudioInput>,
+    ) -> Result<(), String> {
+        let mut encoding = settings.encoding.clone();
+        if let Err(e) = encoding.validate() {
+            log::warn!("[record] Invalid encoding profile, using defaults: {}", e);
+            encoding = EncodingProfile::default();
+        }
+
+        self.logger
+            .set_monitors(monitors.iter().map(|m| m.monitor.clone()).collect())?;
+        if settings.redaction.enabled {
+            self.logger.set_redaction(settings.redaction.clone())?;
+        }
+        if let Some(target) = target {
+            start_target_tracking(target, &self.logger)?;
+        }
+
+        // Audio is recorded once, into the first monitor's video
+        let mut audio_inputs = Some(audio_inputs);
+        let mut recorders = self.recorders.lock().map_err(|e| e.to_string())?;
+        for (display, monitor) in displays.iter().zip(monitors) {
+            recorders.push(Recorder::new(
+                &self.dir.join(&monitor.video),
+                display,
+                target,
+                &encoding,
+                audio_inputs.take().unwrap_or_default(),
+            )?);
+        }
+        for_each_recorder(&mut recorders, Recorder::start)?;
+        drop(recorders);
+
+        // Start input listener
+        let listener = input::start_input_listener(app.clone(), settings.input_sampling.clone())?;
+        *self.listener.lock().map_err(|e| e.to_string())? = Some(listener);
+
+        // Start dump-tree polling
+        axtree::start_dump_tree_polling(app.clone())
+    }
+
+    // Stops every capture of the session. Each step runs even if an earlier one failed, so
+    // the recorders are always stopped and the log closed; the first error is returned.
+    fn stop_captures(&self) -> Result<(), String> {
+        let mut result = Ok(());
+
+        // Stop input listening first
+        let listener = self
+            .listener
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(mut listener) = listener {
+            listener.stop();
+        }
+
+        // Write out anything still waiting for ffmpeg's first frame
+        let synced = self.sync_video_origin();
+        keep_first_error(&mut result, "sync the video origin", synced);
+        keep_first_error(&mut result, "flush the input log", self.logger.flush());
+
+        // Stop dump-tree polling
+        let polling = axtree::stop_dump_tree_polling();
+        keep_first_error(&mut result, "stop dump-tree polling", polling);
+
+        let tracker = capture::stop_window_tracker();
+        keep_first_error(&mut result, "stop the window tracker", tracker);
+
+        let mut recorders = self
+            .recorders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let stopped = for_each_recorder(&mut recorders, Recorder::stop);
+        keep_first_error(&mut result, "stop the recorders", stopped);
+
+        // After the recorders, so ffmpeg's last output still makes it into the log
+        keep_first_error(&mut result, "close the input log", self.logger.close());
+        result
+    }
+
+    // Stops every capture of the session and completes its meta.json, even if stopping
+    // something failed
+    fn finish(&self, reason: Option<String>) -> Result<(), String> {
+        let mut result = Ok(());
+
+        // Stopping while paused closes the open pause so it is excluded from the duration
+        {
+            let mut clock = self.clock.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(paused_at) = clock.paused_at.take() {
+                let recorded = self.record_pause(&mut clock, paused_at);
+                keep_first_error(&mut result, "record the last pause", recorded);
+            }
+        }
+
+        result = result.and(self.stop_captures());
+
+        let duration = self.active_time().as_secs();
+        let completed = self.update_meta(|meta| {
+            meta.duration_seconds = duration;
+            meta.status = "completed".to_string();
+            meta.reason = reason;
+        });
+        keep_first_error(&mut result, "complete meta.json", completed);
+        result
+    }
+}
+
+// Logs a failed step and keeps the first error in `result`, so the steps after it still run
+fn keep_first_error(result: &mut Result<(), String>, step: &str, outcome: Result<(), String>) {
+    if let Err(e) = outcome {
+        log::error!("[record] Failed to {}: {}", step, e);
+        if result.is_ok() {
+            *result = Err(e);
+        }
+    }
+}
+
+// How often the watchdog checks the recorders and emits `recording-health`
+const HEALTH_INTERVAL: Duration = Duration::from_secs(2);
+
+// Global state for the active session and overlay
 lazy_static::lazy_static! {
-    static ref RECORDER_STATE: Arc<Mutex<Option<Recorder>>> = Arc::new(Mutex::new(None));
+    static ref ACTIVE_SESSION: Mutex<Option<Arc<RecordingSession>>> = Mutex::new(None);
+    // Held by start_recording until the new session is in place
+    static ref START_LOCK: Mutex<()> = Mutex::new(());
     static ref RECORDING_STATE: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(Some("off".to_string())));
     static ref OVERLAY_WINDOW_STATE: Mutex<Option<tauri::WebviewWindow>> = Mutex::new(None);
-    static ref LOGGER_STATE: Arc<Mutex<Option<Logger>>> = Arc::new(Mutex::new(None));
+}
+
+fn active_session() -> Option<Arc<RecordingSession>> {
+    ACTIVE_SESSION.lock().ok().and_then(|session| session.clone())
+}
+
+/// Whether a recording session is in progress
+pub fn is_recording() -> bool {
+    active_session().is_some()
 }
 
 fn get_session_path(app: &tauri::AppHandle) -> Result<(PathBuf, String), String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
+    let recordings_dir = storage::recordings_dir(&app)?;
 
     std::fs::create_dir_all(&recordings_dir)
         .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
@@ -233,11 +969,7 @@ fn get_session_path(app: &tauri::AppHandle) -> Result<(PathBuf, String), String>
 }
 
 pub async fn list_recordings(app: tauri::AppHandle) -> Result<Vec<RecordingMeta>, String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
+    let recordings_dir = storage::recordings_dir(&app)?;
 
     if !recordings_dir.exists() {
         return Ok(Vec::new());
@@ -290,6 +1022,82 @@ pub fn set_rec_state(
     Ok(())
 }
 
+/// Moves the recording into the "error" state, telling the UI why
+pub fn set_rec_error(app: &tauri::AppHandle, reason: String) -> Result<(), String> {
+    let mut recording_state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
+    *recording_state = Some("error".to_string());
+    app.emit(
+        "recording-status",
+        serde_json::json!({
+            "state": "error",
+            "reason": reason
+        }),
+    )
+    .map_err(|e| format!("Failed to emit recording status: {}", e))?;
+    Ok(())
+}
+
+// Watches the session until it ends, emitting `recording-health` with one entry per monitor
+// and switching to the "error" state if an encoder dies or stalls. Also stops the recording
+// once one of the configured limits is reached.
+fn start_health_watchdog(
+    app: tauri::AppHandle,
+    session: Arc<RecordingSession>,
+    limits: RecordingLimits,
+) {
+    let mut limit_monitor = LimitMonitor::new(limits, session.dir.clone());
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(HEALTH_INTERVAL);
+        if !active_session().is_some_and(|active| Arc::ptr_eq(&active, &session)) {
+            break;
+        }
+
+        if let Err(e) = session.sync_video_origin() {
+            log::warn!("[record] Failed to align the input log: {}", e);
+        }
+
+        let reports = match session.health() {
+            Ok(reports) => reports,
+            Err(reason) => {
+                log::error!("[record] Recording failed: {}", reason);
+                let _ = set_rec_error(&app, reason);
+                break;
+            }
+        };
+
+        if let Err(e) = app.emit(
+            "recording-health",
+            serde_json::json!({
+                "monitors": reports,
+                "input_log": session.logger.stats()
+            }),
+        ) {
+            log::warn!("[record] Failed to emit recording health: {}", e);
+        }
+
+        if let Some(reason) = limit_monitor.check(&app, session.active_time()) {
+            log::info!("[record] Stopping recording: {}", reason);
+            let _ = app.emit(
+                "recording-limit-reached",
+                serde_json::json!({
+                    "reason": reason
+                }),
+            );
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let quest_state = app.state::<QuestState>();
+                if let Err(e) =
+                    stop_recording(app.clone(), quest_state, Some(reason.to_string())).await
+                {
+                    log::error!("[record] Failed to stop recording at limit: {}", e);
+                }
+            });
+            break;
+        }
+    });
+}
+
 pub async fn get_recording_state() -> Result<String, String> {
     let recording_state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
     recording_state
@@ -300,43 +1108,97 @@ pub async fn get_recording_state() -> Result<String, String> {
 
 pub async fn start_recording(
     app: tauri::AppHandle,
-    quest_state: State<'_, QuestState>,
     quest: Option<Quest>,
     display: Option<MonitorInfo>,
+    displays: Option<Vec<MonitorInfo>>,
+    target: Option<CaptureTarget>,
+    audio: Option<AudioCapture>,
+) -> Result<(), String> {
+    // Starting waits on ffmpeg and, on Wayland, on the user answering the screen cast
+    // portal's source picker, so it runs on a blocking thread instead of a runtime worker
+    tauri::async_runtime::spawn_blocking(move || {
+        start_recording_blocking(&app, quest, display, displays, target, audio)
+    })
+    .await
+    .map_err(|e| format!("Failed to start recording: {}", e))?
+}
+
+fn start_recording_blocking(
+    app: &tauri::AppHandle,
+    quest: Option<Quest>,
+    display: Option<MonitorInfo>,
+    displays: Option<Vec<MonitorInfo>>,
+    target: Option<CaptureTarget>,
+    audio: Option<AudioCapture>,
 ) -> Result<(), String> {
+    let quest_state = app.state::<QuestState>();
+
     // Start screen recording
-    let mut recorder_state = RECORDER_STATE.lock().map_err(|e| e.to_string())?;
-    if recorder_state.is_some() {
-        set_rec_state(&app, "recording".to_string(), None)?;
+    let _start_guard = START_LOCK.lock().map_err(|e| e.to_string())?;
+    if is_recording() {
+        set_rec_state(app, "recording".to_string(), None)?;
         return Err("Recording already in progress".to_string());
     }
 
-    set_rec_state(&app, "starting".to_string(), None)?;
+    set_rec_state(app, "starting".to_string(), None)?;
 
-    //processing screen recording
-    let displays = DisplayInfo::all().map_err(|e| format!("Failed to get display info: {}", e))?;
-    let recorded_display = match display
-    {
-        Some(m) => {
-            displays
-                .iter()
-                .find(|d| d.x == m.x && d.y == m.y)
-                .or_else(|| displays.first())
-                .ok_or_else(|| "No displays found".to_string())?
-        },
-        None => {
-            displays
-                .iter()
-                .find(|d| d.is_primary)
-                .or_else(|| displays.first())
-                .ok_or_else(|| "No displays found".to_string())?
+    if let Err(e) = begin_recording(app, &quest_state, quest, display, displays, target, audio) {
+        log::error!("[record] Failed to start recording: {}", e);
+        if let Err(e) = close_overlay_window() {
+            log::warn!("[record] {}", e);
         }
+        *quest_state.current_quest.lock().unwrap() = None;
+        set_rec_state(app, "off".to_string(), None)?;
+        return Err(e);
+    }
+
+    // Only once the recorders are running
+    set_rec_state(app, "recording".to_string(), None)?;
+    Ok(())
+}
+
+// Sets up the session and starts its captures. Anything started before a failure is stopped
+// again here, apart from the overlay window and quest that `start_recording` clears.
+fn begin_recording(
+    app: &tauri::AppHandle,
+    quest_state: &QuestState,
+    quest: Option<Quest>,
+    display: Option<MonitorInfo>,
+    displays: Option<Vec<MonitorInfo>>,
+    target: Option<CaptureTarget>,
+    audio: Option<AudioCapture>,
+) -> Result<(), String> {
+    //processing screen recording
+    let all_displays =
+        DisplayInfo::all().map_err(|e| format!("Failed to get display info: {}", e))?;
+    // A window or region target is recorded on the monitor it sits on
+    let target = target.map(|t| capture::resolve_target(&t)).transpose()?;
+    let recorded_displays = match &target {
+        Some(target) => vec![target_display(&all_displays, &target.area)?],
+        None => select_displays(&all_displays, display, displays)?,
     };
+    let recorded_display = recorded_displays[0];
+
+    // The portal hands out a single stream, so Wayland sessions record one monitor
+    #[cfg(target_os = "linux")]
+    if recorded_displays.len() > 1 && wayland::is_wayland_session() {
+        return Err("Recording multiple monitors is not supported on Wayland".to_string());
+    }
 
     // Initialize FFmpeg
     init_ffmpeg()?;
 
-    create_overlay_window(&app, recorded_display)?;
+    let settings = Settings::load(app);
+    let recordings_dir = storage::recordings_dir(app)?;
+    limits::check_free_space(&recordings_dir, &settings.limits)?;
+
+    let audio = audio.filter(|a| a.is_enabled());
+    let audio_inputs = match &audio {
+        Some(audio) => audio::resolve_audio_inputs(audio)?,
+        None => Vec::new(),
+    };
+
+    create_overlay_window(app, recorded_display)?;
 
     // Store quest data in state if available
     if let Some(quest_data) = &quest {
@@ -353,12 +1215,17 @@ pub async fn start_recording(
         .map_err(|e| format!("Failed to emit quest data: {}", e))?;
     }
 
-    let (session_dir, timestamp) = get_session_path(&app)?;
-
-    // Store the recording ID
-    *quest_state.current_recording_id.lock().unwrap() = Some(timestamp.clone());
+    let (session_dir, timestamp) = get_session_path(app)?;
 
-    let video_path = session_dir.join("recording.mp4");
+    let monitors: Vec<RecordedMonitor> = recorded_displays
+        .iter()
+        .enumerate()
+        .map(|(index, display)| RecordedMonitor {
+            index,
+            video: monitor_video_name(index),
+            monitor: MonitorInfo::from_display(display),
+        })
+        .collect();
 
     // Create and save initial meta file
     let meta = RecordingMeta {
@@ -376,14 +1243,18 @@ pub async fn start_recording(
         arch: tauri_plugin_os::arch().to_string(),
         version: tauri_plugin_os::version().to_string(),
         locale: tauri_plugin_os::locale().unwrap_or_default(),
-        monitor: MonitorInfo {
-            width: recorded_display.width,
-            height: recorded_display.height,
-            x: recorded_display.x,
-            y: recorded_display.y
-        },
+        monitor: MonitorInfo::from_display(recorded_display),
         reason: None,
         quest,
+        pauses: Vec::new(),
+        monitors: monitors.clone(),
+        capture: target.as_ref().map(|t| CaptureInfo {
+            target: t.target.clone(),
+            area: t.area,
+        }),
+        audio,
+        pinned: false,
+        uploaded_at: None,
     };
 
     fs::write(
@@ -393,26 +1264,55 @@ pub async fn start_recording(
     )
     .map_err(|e| format!("Failed to write meta file: {}", e))?;
 
-    *quest_state.recording_start_time.lock().unwrap() = Some(Local::now());
-
-    set_rec_state(&app, "recording".to_string(), None)?;
-
-    let mut recorder = Recorder::new(&video_path, &recorded_display)?;
-    recorder.start()?;
-    *recorder_state = Some(recorder);
-
-    // Start input logging and listening
-    let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
-    if log_state.is_none() {
-        *log_state = Some(Logger::new(session_dir.clone())?);
+    // Start input logging
+    let logger = Logger::new(session_dir.clone())?;
+    let session = Arc::new(RecordingSession {
+        id: timestamp,
+        dir: session_dir,
+        started_at: Local::now(),
+        clock: Mutex::new(PauseClock::default()),
+        recorders: Mutex::new(Vec::new()),
+        logger,
+        listener: Mutex::new(None),
+    });
+
+    let started = session
+        .start_captures(
+            app,
+            &settings,
+            target.as_ref(),
+            &recorded_displays,
+            &monitors,
+            audio_inputs,
+        )
+        .and_then(|_| {
+            *ACTIVE_SESSION.lock().map_err(|e| e.to_string())? = Some(session.clone());
+            Ok(())
+        });
+    if let Err(e) = started {
+        if let Err(e) = session.stop_captures() {
+            log::warn!("[record] Failed to stop the unstarted session: {}", e);
+        }
+        // Nothing was recorded, so no half-written session is left for recovery to pick up
+        if let Err(e) = fs::remove_dir_all(&session.dir) {
+            log::warn!("[record] Failed to remove session directory: {}", e);
+        }
+        return Err(e);
     }
 
-    // Start input listener
-    input::start_input_listener(app.clone())?;
+    start_health_watchdog(app.clone(), session, settings.limits);
 
-    // Start dump-tree polling
-    axtree::start_dump_tree_polling(app.clone())?;
+    Ok(())
+}
 
+// Closes the overlay window of the session, if there is one
+fn close_overlay_window() -> Result<(), String> {
+    let mut overlay_state = OVERLAY_WINDOW_STATE.lock().map_err(|e| e.to_string())?;
+    if let Some(window) = overlay_state.take() {
+        window
+            .close()
+            .map_err(|e| format!("Failed to close overlay window: {}", e))?;
+    }
     Ok(())
 }
 
@@ -421,116 +1321,77 @@ pub async fn stop_recording(
     quest_state: State<'_, QuestState>,
     reason: Option<String>,
 ) -> Result<String, String> {
-    // Emit recording stopping event
-    set_rec_state(&app, "stopping".to_string(), None)?;
-
-    // Stop input logging and listening first
-    let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
-    *log_state = None;
-
-    // Stop input listener
-    input::stop_input_listener()?;
-
-    // Stop dump-tree polling
-    axtree::stop_dump_tree_polling()?;
-
-    let mut rec_state = RECORDER_STATE.lock().map_err(|e| e.to_string())?;
-    if let Some(mut recorder) = rec_state.take() {
-        recorder.stop()?;
-    }
-
-    // Update meta file with duration
-    if let Some(start_time) = *quest_state.recording_start_time.lock().unwrap() {
-        let duration = Local::now().signed_duration_since(start_time).num_seconds() as u64;
-
-        let recordings_dir = app
-            .path()
-            .app_local_data_dir()
-            .map_err(|e| format!("Failed to get app data directory: {}", e))?
-            .join("recordings");
-
-        // Find the most recent recording directory
-        let mut entries: Vec<_> = fs::read_dir(&recordings_dir)
-            .map_err(|e| format!("Failed to read recordings directory: {}", e))?
-            .collect::<Result<_, _>>()
-            .map_err(|e| format!("Failed to read directory entries: {}", e))?;
-
-        entries
-            .sort_by_key(|entry| std::cmp::Reverse(entry.metadata().unwrap().modified().unwrap()));
-
-        if let Some(latest_dir) = entries.first() {
-            let meta_path = latest_dir.path().join("meta.json");
-            if meta_path.exists() {
-                let meta_str = fs::read_to_string(&meta_path)
-                    .map_err(|e| format!("Failed to read meta file: {}", e))?;
-                let mut meta: RecordingMeta = serde_json::from_str(&meta_str)
-                    .map_err(|e| format!("Failed to parse meta file: {}", e))?;
-
-                meta.duration_seconds = duration;
-                meta.status = "completed".to_string();
-                meta.reason = reason;
-
-                fs::write(
-                    &meta_path,
-                    serde_json::to_string_pretty(&meta)
-                        .map_err(|e| format!("Failed to serialize meta: {}", e))?,
-                )
-                .map_err(|e| format!("Failed to write meta file: {}", e))?;
-            }
-        }
-    }
+    let session = ACTIVE_SESSION
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take()
+        .ok_or_else(|| "No recording in progress".to_string())?;
 
-    // destroy the overlay window
-    let mut overlay_state = OVERLAY_WINDOW_STATE.lock().map_err(|e| e.to_string())?;
-    if let Some(window) = overlay_state.take() {
-        window
-            .close()
-            .map_err(|e| format!("Failed to close overlay window: {}", e))?;
-    }
+    // The session is no longer active, so everything below runs even if a step fails and
+    // the first error is returned at the end
+    let mut result = Ok(());
 
-    // Find the most recent recording directory to get its ID
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
+    // Emit recording stopping event
+    let stopping = set_rec_state(&app, "stopping".to_string(), None);
+    keep_first_error(&mut result, "emit the stopping state", stopping);
 
-    let mut entries: Vec<_> = fs::read_dir(&recordings_dir)
-        .map_err(|e| format!("Failed to read recordings directory: {}", e))?
-        .collect::<Result<_, _>>()
-        .map_err(|e| format!("Failed to read directory entries: {}", e))?;
+    result = result.and(session.finish(reason));
 
-    entries.sort_by_key(|entry| std::cmp::Reverse(entry.metadata().unwrap().modified().unwrap()));
+    // destroy the overlay window
+    let closed = close_overlay_window();
+    keep_first_error(&mut result, "close the overlay window", closed);
 
     // Clear the current quest
     *quest_state.current_quest.lock().unwrap() = None;
 
-    // Get the recording ID from state
-    if let Some(recording_id) = quest_state.current_recording_id.lock().unwrap().take() {
-        set_rec_state(&app, "saved".to_string(), Some(recording_id.clone()))?;
-        set_rec_state(&app, "off".to_string(), None)?;
+    if result.is_ok() {
+        set_rec_state(&app, "saved".to_string(), Some(session.id.clone()))?;
+    }
+    set_rec_state(&app, "off".to_string(), None)?;
 
-        Ok(recording_id.to_string())
-    } else {
-        Err("No recording ID found".to_string())
+    let retention_handle = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = retention::enforce_retention(&retention_handle) {
+            log::error!("[Retention] Failed to apply retention policy: {}", e);
+        }
+    });
+
+    result.map(|_| session.id.clone())
+}
+
+pub async fn pause_recording(app: tauri::AppHandle) -> Result<(), String> {
+    let session = active_session().ok_or_else(|| "No recording in progress".to_string())?;
+    if let Err(e) = session.pause() {
+        let _ = set_rec_error(&app, format!("Failed to pause recording: {}", e));
+        return Err(e);
     }
+
+    set_rec_state(&app, "paused".to_string(), None)?;
+    Ok(())
 }
 
-pub fn log_input(event: serde_json::Value) -> Result<(), String> {
-    if let Ok(mut state) = LOGGER_STATE.lock() {
-        if let Some(logger) = state.as_mut() {
-            logger.log_event(event)?;
-        }
+pub async fn resume_recording(app: tauri::AppHandle) -> Result<(), String> {
+    let session = active_session().ok_or_else(|| "No recording in progress".to_string())?;
+    if let Err(e) = session.resume() {
+        let _ = set_rec_error(&app, format!("Failed to resume recording: {}", e));
+        return Err(e);
+    }
+
+    set_rec_state(&app, "recording".to_string(), None)?;
+    Ok(())
+}
+
+pub fn log_input(event: InputEvent) -> Result<(), String> {
+    if let Some(session) = active_session() {
+        session.log_input(event)?;
     }
     Ok(())
 }
 
 // #[cfg(not(target_os = "macos"))]
 pub fn log_ffmpeg(output: &str, is_stderr: bool) -> Result<(), String> {
-    if let Ok(mut state) = LOGGER_STATE.lock() {
-        if let Some(logger) = state.as_mut() {
-            logger.log_ffmpeg(output, is_stderr)?;
-        }
+    if let Some(session) = active_session() {
+        session.log_ffmpeg(output, is_stderr)?;
     }
     Ok(())
 }
@@ -542,12 +1403,7 @@ pub async fn get_recording_file(
     as_base64: Option<bool>,
     as_path: Option<bool>,
 ) -> Result<String, String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(&recording_id);
+    let recordings_dir = storage::recording_dir(&app, &recording_id)?;
 
     let file_path = recordings_dir.join(&filename);
     if !file_path.exists() {
@@ -600,12 +1456,7 @@ pub async fn write_recording_file(
     content: String,
 ) -> Result<(), String> {
     // Get the path to the recording directory
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(&recording_id);
+    let recordings_dir = storage::recording_dir(&app, &recording_id)?;
 
     // Check if the recording directory exists
     if !recordings_dir.exists() {
@@ -625,11 +1476,7 @@ pub async fn open_recording_folder(
     app: tauri::AppHandle,
     recording_id: String,
 ) -> Result<(), String> {
-    let mut recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
+    let mut recordings_dir = storage::recordings_dir(&app)?;
     // only add the ID if requested
     if !recording_id.is_empty() {
         recordings_dir = recordings_dir.join(&recording_id);
@@ -646,12 +1493,7 @@ pub async fn open_recording_folder(
 }
 
 pub async fn delete_recording(app: tauri::AppHandle, recording_id: String) -> Result<(), String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(&recording_id);
+    let recordings_dir = storage::recording_dir(&app, &recording_id)?;
 
     if !recordings_dir.exists() {
         return Err(format!("Recording folder not found: {}", recording_id));
@@ -669,6 +1511,9 @@ pub struct PrivateRange {
     end: f64,
     #[allow(dead_code)]
     count: i32, // this is needed for processing, but not accessed in Rust
+    // Parts of the frame to hide; without any the whole frame is blacked out
+    #[serde(default)]
+    regions: Vec<PrivateRegion>,
 }
 
 // Helper function to read and parse a JSON file
@@ -678,11 +1523,57 @@ fn read_json_file<T: DeserializeOwned>(path: &PathBuf) -> Result<T, String> {
     serde_json::from_reader(reader).map_err(|e| format!("Failed to parse JSON: {}", e))
 }
 
-// Helper function to process video with FFmpeg to black out private ranges
+// Builds the filters that blur or pixelate `region` between `start` and `end` (in seconds),
+// reading the video from `input` and writing it to `[r{index}]`. `scale` maps the recorded
+// area onto the video, which the encoding profile may have downscaled.
+fn region_filter(
+    region: &PrivateRegion,
+    (start, end): (f64, f64),
+    scale: (f64, f64),
+    (width, height): (u32, u32),
+    input: &str,
+    index: usize,
+) -> Option<String> {
+    // yuv420p needs even offsets and sizes
+    let left = ((region.x * scale.0).floor().max(0.0) as u32).min(width) & !1;
+    let top = ((region.y * scale.1).floor().max(0.0) as u32).min(height) & !1;
+    let right = (((region.x + region.w) * scale.0).ceil().max(0.0) as u32).min(width);
+    let bottom = (((region.y + region.h) * scale.1).ceil().max(0.0) as u32).min(height);
+    let w = right.saturating_sub(left) & !1;
+    let h = bottom.saturating_sub(top) & !1;
+    if w < 4 || h < 4 {
+        log::info!(
+            "[process_video] Skipping region outside the video: {:?}",
+            region
+        );
+        return None;
+    }
+
+    let hide = match region.style {
+        // The chroma planes are half the size, which bounds the radius
+        RegionStyle::Blur => format!("boxblur={}:3", (w.min(h) / 4).min(20)),
+        RegionStyle::Pixelate => format!(
+            "scale={}:{}:flags=area,scale={}:{}:flags=neighbor",
+            (w / 16).max(1),
+            (h / 16).max(1),
+            w,
+            h
+        ),
+    };
+    Some(format!(
+        "{input}split[r{i}a][r{i}b];[r{i}b]crop={w}:{h}:{left}:{top},{hide}[r{i}c];\
+         [r{i}a][r{i}c]overlay={left}:{top}:enable='between(t,{start},{end})'[r{i}]",
+        i = index
+    ))
+}
+
+// Helper function to process video with FFmpeg to hide private ranges: regions are blurred
+// or pixelated, ranges without any are blacked out
 fn process_video_with_private_ranges(
     input_path: &PathBuf,
     output_path: &PathBuf,
     private_ranges: &[PrivateRange],
+    area: Option<RecordedArea>,
 ) -> Result<(), String> {
     // If no private ranges, just copy the file
     if private_ranges.is_empty() {
@@ -783,12 +1674,76 @@ fn process_video_with_private_ranges(
         .parse()
         .map_err(|e| format!("Failed to parse video height: {}", e))?;
 
+    // Recordings with audio capture get their audio muted in the same ranges
+    let mut a_command = Command::new(ffprobe);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        a_command.creation_flags(0x08000000); // CREATE_NO_WINDOW constant
+    }
+    let audio_output = a_command
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "a",
+            "-show_entries",
+            "stream=index",
+            "-of",
+            "csv=p=0",
+            input_path.to_str().unwrap(),
+        ])
+        .output()
+        .map_err(|e| format!("Failed to execute ffprobe for audio streams: {}", e))?;
+    let has_audio = !String::from_utf8_lossy(&audio_output.stdout)
+        .trim()
+        .is_empty();
+    log::info!("[process_video] Video has audio: {}", has_audio);
+
+    // Ranges with regions keep the frame and only hide those parts of it
+    let monitor = area.as_ref().map_or(0, |area| area.monitor);
+    let scale = match &area {
+        Some(area) if area.width > 0 && area.height > 0 => (
+            width as f64 / area.width as f64,
+            height as f64 / area.height as f64,
+        ),
+        _ => (1.0, 1.0),
+    };
+    let mut region_filters = Vec::new();
+    for range in private_ranges {
+        let seconds = (range.start / 1000.0, range.end / 1000.0);
+        for region in &range.regions {
+            if region.monitor.is_some_and(|index| index != monitor) {
+                continue;
+            }
+            let index = region_filters.len();
+            let input = match index {
+                0 => "[0:v]".to_string(),
+                _ => format!("[r{}]", index - 1),
+            };
+            if let Some(filter) =
+                region_filter(region, seconds, scale, (width, height), &input, index)
+            {
+                region_filters.push(filter);
+            }
+        }
+    }
+
     // Convert milliseconds to seconds for FFmpeg
     let ranges: Vec<(f64, f64)> = private_ranges
         .iter()
+        .filter(|range| range.regions.is_empty())
         .map(|range| (range.start / 1000.0, range.end / 1000.0))
         .collect();
 
+    // Regions may all belong to another monitor's video
+    if ranges.is_empty() && region_filters.is_empty() {
+        log::info!("[process_video] Nothing to hide in this video, copying video file");
+        fs::copy(input_path, output_path)
+            .map_err(|e| format!("Failed to copy video file: {}", e))?;
+        return Ok(());
+    }
+
     // Sort ranges by start time
     let mut sorted_ranges = ranges.clone();
     sorted_ranges.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
@@ -819,6 +1774,16 @@ fn process_video_with_private_ranges(
     let mut last_end = 0.0;
     let mut segment_index = 0;
 
+    // With regions to hide, segments are cut from the filtered video instead of the input
+    let mut video_sources = 0;
+    let mut video_source = || {
+        video_sources += 1;
+        match region_filters.is_empty() {
+            true => "[0:v]".to_string(),
+            false => format!("[src{}]", video_sources - 1),
+        }
+    };
+
     log::info!(
         "[process_video] Building filter graph for {} private ranges",
         merged_ranges.len()
@@ -846,10 +1811,20 @@ fn process_video_with_private_ranges(
                 start
             );
             filter_parts.push(format!(
-                "[0:v]trim=start={}:end={},setpts=PTS-STARTPTS[v{}]",
-                last_end, start, segment_index
+                "{}trim=start={}:end={},setpts=PTS-STARTPTS[v{}]",
+                video_source(),
+                last_end,
+                start,
+                segment_index
             ));
             segment_inputs.push(format!("[v{}]", segment_index));
+            if has_audio {
+                filter_parts.push(format!(
+                    "[0:a]atrim=start={}:end={},asetpts=PTS-STARTPTS[a{}]",
+                    last_end, start, segment_index
+                ));
+                segment_inputs.push(format!("[a{}]", segment_index));
+            }
             segment_index += 1;
         }
 
@@ -866,6 +1841,14 @@ fn process_video_with_private_ranges(
             width, height, black_duration, segment_index
         ));
         segment_inputs.push(format!("[v{}]", segment_index));
+        if has_audio {
+            // Keep the original samples' timing but silence them
+            filter_parts.push(format!(
+                "[0:a]atrim=start={}:end={},asetpts=PTS-STARTPTS,volume=0[a{}]",
+                start, end, segment_index
+            ));
+            segment_inputs.push(format!("[a{}]", segment_index));
+        }
         segment_index += 1;
 
         last_end = end;
@@ -879,13 +1862,35 @@ fn process_video_with_private_ranges(
             duration
         );
         filter_parts.push(format!(
-            "[0:v]trim=start={}:end={},setpts=PTS-STARTPTS[v{}]",
-            last_end, duration, segment_index
+            "{}trim=start={}:end={},setpts=PTS-STARTPTS[v{}]",
+            video_source(),
+            last_end,
+            duration,
+            segment_index
         ));
         segment_inputs.push(format!("[v{}]", segment_index));
+        if has_audio {
+            filter_parts.push(format!(
+                "[0:a]atrim=start={}:end={},asetpts=PTS-STARTPTS[a{}]",
+                last_end, duration, segment_index
+            ));
+            segment_inputs.push(format!("[a{}]", segment_index));
+        }
         segment_index += 1;
     }
 
+    // Regions hidden in frames that end up blacked out anyway need no filters
+    if !region_filters.is_empty() && video_sources > 0 {
+        let outputs: String = (0..video_sources).map(|i| format!("[src{}]", i)).collect();
+        region_filters.push(format!(
+            "[r{}]split={}{}",
+            region_filters.len() - 1,
+            video_sources,
+            outputs
+        ));
+        filter_parts.splice(0..0, region_filters);
+    }
+
     // If no segments were created (e.g., all ranges were invalid), just copy the video
     if segment_index == 0 {
         log::info!("[process_video] No valid segments to process, copying video file");
@@ -895,11 +1900,19 @@ fn process_video_with_private_ranges(
     }
 
     // Create concat filter
-    let concat_filter = format!(
-        "{}concat=n={}:v=1:a=0[outv]",
-        segment_inputs.join(""),
-        segment_index
-    );
+    let concat_filter = if has_audio {
+        format!(
+            "{}concat=n={}:v=1:a=1[outv][outa]",
+            segment_inputs.join(""),
+            segment_index
+        )
+    } else {
+        format!(
+            "{}concat=n={}:v=1:a=0[outv]",
+            segment_inputs.join(""),
+            segment_index
+        )
+    };
 
     // Build complete filter graph
     let filter_graph = format!("{};{}", filter_parts.join(";"), concat_filter);
@@ -917,18 +1930,24 @@ fn process_video_with_private_ranges(
         ffmpeg_command.creation_flags(0x08000000); // CREATE_NO_WINDOW constant
     }
 
+    ffmpeg_command.args([
+        "-i",
+        input_path.to_str().unwrap(),
+        "-filter_complex",
+        &filter_graph,
+        "-map",
+        "[outv]",
+        "-c:v",
+        "libx264",
+        "-preset",
+        "fast",
+    ]);
+    if has_audio {
+        ffmpeg_command.args(["-map", "[outa]", "-c:a", "aac", "-b:a", "128k"]);
+    }
+
     let status = ffmpeg_command
         .args([
-            "-i",
-            input_path.to_str().unwrap(),
-            "-filter_complex",
-            &filter_graph,
-            "-map",
-            "[outv]",
-            "-c:v",
-            "libx264",
-            "-preset",
-            "fast",
             "-y", // Overwrite output file if it exists
             output_path.to_str().unwrap(),
         ])
@@ -942,118 +1961,177 @@ fn process_video_with_private_ranges(
     Ok(())
 }
 
-// Helper function to filter input log events based on private ranges
+// Marker at `offset_ms`, its wall-clock time worked out from the `(time, offset_ms)` of an
+// entry near it
+fn private_marker(event: InputEvent, offset_ms: f64, (time, anchor_ms): (i64, i64)) -> LogEntry {
+    let offset_ms = offset_ms.round() as i64;
+    LogEntry {
+        event,
+        time: time + offset_ms - anchor_ms,
+        offset_ms: Some(offset_ms),
+        monitor: None,
+    }
+}
+
+// Helper function to filter input log events based on private ranges, either dropping them
+// or scrubbing them between `private_start`/`private_end` markers
 fn filter_input_log(
     input_path: &PathBuf,
     output_path: &PathBuf,
     private_ranges: &[PrivateRange],
+    mode: PrivateEventMode,
 ) -> Result<(), String> {
-    let input_file =
-        File::open(input_path).map_err(|e| format!("Failed to open input log: {}", e))?;
-    let mut reader = BufReader::new(input_file);
-
-    // Read all lines into memory
-    let mut lines = Vec::new();
-    let mut line = String::new();
-    while reader
-        .read_line(&mut line)
-        .map_err(|e| format!("Failed to read line: {}", e))?
-        > 0
-    {
-        if !line.trim().is_empty() {
-            lines.push(line.clone());
-        }
-        line.clear();
-    }
-
-    // Get reference timestamp from first event
-    let reference_timestamp = if !lines.is_empty() {
-        let first_line = &lines[0];
-        let first_event: serde_json::Value = serde_json::from_str(first_line)
-            .map_err(|e| format!("Failed to parse first event JSON: {}", e))?;
-
-        first_event
-            .get("time")
-            .and_then(|t| t.as_i64())
-            .unwrap_or(0)
-    } else {
-        0 // Default if no lines
-    };
-
+    // The reader fills in `offset_ms` for logs that predate it
+    let log = input_log::read_input_log(input_path)?;
     log::info!(
-        "[filter_input_log] Using reference timestamp: {}",
-        reference_timestamp
+        "[filter_input_log] Read {} events (schema version {})",
+        log.entries.len(),
+        log.schema_version
     );
 
+    // Overlapping ranges get a single pair of markers
+    let mut ranges: Vec<(f64, f64)> = private_ranges
+        .iter()
+        .map(|range| (range.start, range.end))
+        .collect();
+    ranges.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let mut merged_ranges: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in ranges {
+        match merged_ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged_ranges.push((start, end)),
+        }
+    }
+
     let output_file =
         File::create(output_path).map_err(|e| format!("Failed to create output file: {}", e))?;
     let mut writer = BufWriter::new(output_file);
+    let mut write = |entry: &LogEntry| -> Result<(), String> {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize event: {}", e))?;
+        writeln!(writer, "{}", line).map_err(|e| format!("Failed to write to output: {}", e))
+    };
 
-    // Process each line
-    for line in lines {
-        if line.trim().is_empty() {
+    let mut upcoming = merged_ranges.iter().copied().peekable();
+    // End of the range being written and how many of its events were scrubbed
+    let mut open: Option<(f64, usize)> = None;
+    let mut anchor = None;
+    let mut removed = 0;
+
+    for mut entry in log.entries {
+        let here = (entry.time, entry.offset_ms.unwrap_or_default());
+        anchor = Some(here);
+        // The header describes the whole session and is always kept
+        if matches!(entry.event, InputEvent::SessionStart { .. }) {
+            write(&entry)?;
             continue;
         }
+        let offset_ms = here.1 as f64;
 
-        // Parse the JSON event
-        let event: serde_json::Value = serde_json::from_str(&line)
-            .map_err(|e| format!("Failed to parse event JSON: {}", e))?;
-
-        // Check if the event has a timestamp
-        if let Some(timestamp) = event.get("time").and_then(|t| t.as_i64()) {
-            // Calculate relative timestamp by subtracting reference timestamp
-            let relative_timestamp = timestamp - reference_timestamp;
-            let relative_timestamp_f64 = relative_timestamp as f64;
+        if mode == PrivateEventMode::Drop {
+            if merged_ranges
+                .iter()
+                .any(|&(start, end)| offset_ms >= start && offset_ms <= end)
+            {
+                removed += 1;
+                continue;
+            }
+            write(&entry)?;
+            continue;
+        }
 
-            // Check if this relative timestamp falls within any private range
-            let is_private = private_ranges.iter().any(|range| {
-                relative_timestamp_f64 >= range.start && relative_timestamp_f64 <= range.end
-            });
+        // Close the ranges that ended before this event and open the one it falls in
+        loop {
+            if let Some((end, redacted)) = open {
+                if offset_ms <= end {
+                    break;
+                }
+                let marker = InputEvent::PrivateEnd { redacted };
+                write(&private_marker(marker, end, here))?;
+                open = None;
+            } else if let Some((start, end)) = upcoming.next_if(|&(start, _)| start <= offset_ms) {
+                write(&private_marker(InputEvent::PrivateStart, start, here))?;
+                open = Some((end, 0));
+            } else {
+                break;
+            }
+        }
 
-            // If not private, write to output
-            if !is_private {
-                writeln!(writer, "{}", line)
-                    .map_err(|e| format!("Failed to write to output: {}", e))?;
+        if let Some((_, redacted)) = open.as_mut() {
+            if redaction::scrub_event(&mut entry.event) {
+                *redacted += 1;
+                removed += 1;
             }
-        } else {
-            // If no timestamp, include the event
-            writeln!(writer, "{}", line)
-                .map_err(|e| format!("Failed to write to output: {}", e))?;
         }
+        write(&entry)?;
     }
 
+    // Ranges reaching past the last event still get their markers
+    if let (PrivateEventMode::Redact, Some(anchor)) = (mode, anchor) {
+        if let Some((end, redacted)) = open {
+            let marker = InputEvent::PrivateEnd { redacted };
+            write(&private_marker(marker, end, anchor))?;
+        }
+        for (start, end) in upcoming {
+            write(&private_marker(InputEvent::PrivateStart, start, anchor))?;
+            let marker = InputEvent::PrivateEnd { redacted: 0 };
+            write(&private_marker(marker, end, anchor))?;
+        }
+    }
+
+    log::info!(
+        "[filter_input_log] {} {} events in {} private ranges",
+        match mode {
+            PrivateEventMode::Redact => "Scrubbed",
+            PrivateEventMode::Drop => "Dropped",
+        },
+        removed,
+        merged_ranges.len()
+    );
+
     writer
         .flush()
         .map_err(|e| format!("Failed to flush output: {}", e))?;
     Ok(())
 }
 
-pub async fn create_recording_zip(
-    app: tauri::AppHandle,
-    recording_id: String,
-) -> Result<Vec<u8>, String> {
+// Builds the upload archive of a recording at `zip_path`, streaming every file from disk
+async fn write_recording_zip(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    zip_path: &Path,
+) -> Result<(), String> {
     log::info!(
         "[create_recording_zip] Starting to create zip for recording ID: {}",
         recording_id
     );
 
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(&recording_id);
+    let recordings_dir = storage::recording_dir(app, recording_id)?;
 
     log::info!(
         "[create_recording_zip] Recording directory: {}",
         recordings_dir.display()
     );
 
-    // Create a buffer to store the zip file
-    let buf = Cursor::new(Vec::new());
-    let mut zip = ZipWriter::new(buf);
-    let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
-    log::info!("[create_recording_zip] Initialized zip writer with Stored compression method");
+    // Sessions can be several GB, so the archive goes straight to disk
+    let zip_file =
+        File::create(zip_path).map_err(|e| format!("Failed to create zip file: {}", e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(zip_file));
+    log::info!(
+        "[create_recording_zip] Writing zip archive to {}",
+        zip_path.display()
+    );
+
+    // Sessions that recorded several monitors have one video per monitor
+    let mut videos = vec!["recording.mp4".to_string()];
+    let meta = read_json_file::<RecordingMeta>(&recordings_dir.join("meta.json")).ok();
+    if let Some(meta) = &meta {
+        for monitor in &meta.monitors {
+            if !videos.contains(&monitor.video) {
+                videos.push(monitor.video.clone());
+            }
+        }
+    }
 
     // Check if private_ranges.json exists
     let private_ranges_path = recordings_dir.join("private_ranges.json");
@@ -1065,7 +2143,7 @@ pub async fn create_recording_zip(
 
     // Create temp directory for processed files if needed
     let temp_dir = if has_private_ranges {
-        let temp_path = recordings_dir.join("temp_private");
+        let temp_path = processed_dir(app, recording_id)?;
         log::info!(
             "[create_recording_zip] Creating temp directory for private ranges processing: {}",
             temp_path.display()
@@ -1098,27 +2176,38 @@ pub async fn create_recording_zip(
             input_log_path.display(),
             temp_input_log_path.display()
         );
-        filter_input_log(&input_log_path, &temp_input_log_path, &private_ranges)?;
+        let mode = Settings::load(app).redaction.private_events;
+        filter_input_log(&input_log_path, &temp_input_log_path, &private_ranges, mode)?;
 
-        // Process video (blackout frames in private ranges)
-        let video_path = recordings_dir.join("recording.mp4");
-        let temp_video_path = temp_dir.join("recording.mp4");
-        log::info!(
-            "[create_recording_zip] Processing video with private ranges from {} to {}",
-            video_path.display(),
-            temp_video_path.display()
-        );
-        process_video_with_private_ranges(&video_path, &temp_video_path, &private_ranges)?;
+        // Process videos (blackout frames in private ranges)
+        for video in &videos {
+            let video_path = recordings_dir.join(video);
+            let temp_video_path = temp_dir.join(video);
+            log::info!(
+                "[create_recording_zip] Processing video with private ranges from {} to {}",
+                video_path.display(),
+                temp_video_path.display()
+            );
+            let area = meta.as_ref().and_then(|meta| meta.recorded_area(video));
+            process_video_with_private_ranges(
+                &video_path,
+                &temp_video_path,
+                &private_ranges,
+                area,
+            )?;
+        }
     }
 
     // Add files to zip
-    let filenames = ["input_log.jsonl", "meta.json", "recording.mp4"];
+    let mut filenames = vec!["input_log.jsonl".to_string(), "meta.json".to_string()];
+    filenames.extend(videos);
     log::info!(
         "[create_recording_zip] Adding {} files to zip archive",
         filenames.len()
     );
 
-    for filename in filenames {
+    for filename in &filenames {
+        let filename = filename.as_str();
         let file_path = if has_private_ranges && filename != "meta.json" && temp_dir.is_some() {
             // Use temp files for input_log and recording
             temp_dir.as_ref().unwrap().join(filename)
@@ -1141,70 +2230,154 @@ pub async fn create_recording_zip(
             return Err(format!("File not found: {}", filename));
         }
 
-        // Get file size for logging
-        let metadata = fs::metadata(&file_path)
-            .map_err(|e| format!("Failed to get metadata for {}: {}", filename, e))?;
-        let file_size = metadata.len();
+        let size = archive::add_file_to_zip(&mut zip, filename, &file_path)?;
         log::info!(
-            "[create_recording_zip] Adding file to zip: {} (size: {} bytes)",
+            "[create_recording_zip] Added {} to zip archive ({} bytes)",
             filename,
-            file_size
-        );
-
-        let mut file =
-            File::open(&file_path).map_err(|e| format!("Failed to open {}: {}", filename, e))?;
-        let mut contents = Vec::new();
-        file.read_to_end(&mut contents)
-            .map_err(|e| format!("Failed to read {}: {}", filename, e))?;
-
-        log::info!(
-            "[create_recording_zip] Read {} bytes from {}",
-            contents.len(),
-            filename
-        );
-
-        zip.start_file(filename, options)
-            .map_err(|e| format!("Failed to add {} to zip: {}", filename, e))?;
-        zip.write_all(&contents)
-            .map_err(|e| format!("Failed to write {} to zip: {}", filename, e))?;
-
-        log::info!(
-            "[create_recording_zip] Successfully added {} to zip archive",
-            filename
+            size
         );
     }
 
     // Finish zip file
+    // Describes input_log.jsonl for downstream tools
+    let schema = serde_json::to_vec_pretty(&input_log::input_log_schema())
+        .map_err(|e| format!("Failed to serialize input log schema: {}", e))?;
+    let options = archive::zip_entry_options("input_log.schema.json", schema.len() as u64);
+    zip.start_file("input_log.schema.json", options)
+        .map_err(|e| format!("Failed to add input_log.schema.json to zip: {}", e))?;
+    zip.write_all(&schema)
+        .map_err(|e| format!("Failed to write input_log.schema.json to zip: {}", e))?;
+
     log::info!("[create_recording_zip] Finalizing zip archive");
-    let buf = zip
-        .finish()
+    zip.finish()
         .map_err(|e| format!("Failed to finalize zip: {}", e))?
-        .into_inner();
+        .flush()
+        .map_err(|e| format!("Failed to flush zip file: {}", e))?;
 
-    let zip_size = buf.len();
+    let zip_size = fs::metadata(zip_path)
+        .map_err(|e| format!("Failed to get zip file size: {}", e))?
+        .len();
     log::info!(
         "[create_recording_zip] Zip archive created successfully, size: {} bytes",
         zip_size
     );
 
-    // Clean up temp directory if it was created
-    // if let Some(temp_dir) = temp_dir {
-    //     log::info!("[create_recording_zip] Cleaning up temp directory: {}", temp_dir.display());
-    //     match fs::remove_dir_all(&temp_dir) {
-    //         Ok(_) => log::info!("[create_recording_zip] Successfully removed temp directory"),
-    //         Err(e) => log::info!("[create_recording_zip] Warning: Failed to remove temp directory: {}", e),
-    //     }
-    // }
-
     log::info!(
         "[create_recording_zip] Completed creating zip for recording ID: {}",
         recording_id
     );
-    Ok(buf)
+    Ok(())
+}
+
+// Where the copies with private ranges hidden are made. It lives in the exports folder so
+// it is never mistaken for a session inside `recordings/`.
+fn processed_dir(app: &tauri::AppHandle, recording_id: &str) -> Result<PathBuf, String> {
+    Ok(storage::exports_dir(app)?.join(format!("{}_private", recording_id)))
+}
+
+// Writes the archive to `zip_path`, removing what was written if it fails, and the processed
+// copies either way
+async fn write_recording_zip_or_clean_up(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    zip_path: &Path,
+) -> Result<(), String> {
+    let result = write_recording_zip(app, recording_id, zip_path).await;
+    if result.is_err() && zip_path.exists() {
+        let _ = fs::remove_file(zip_path);
+    }
+
+    // Older exports made their copies inside the session folder
+    let legacy_dir = storage::recording_dir(app, recording_id)?.join("temp_private");
+    for temp_dir in [processed_dir(app, recording_id)?, legacy_dir] {
+        if !temp_dir.exists() {
+            continue;
+        }
+        log::info!(
+            "[create_recording_zip] Cleaning up temp directory: {}",
+            temp_dir.display()
+        );
+        if let Err(e) = fs::remove_dir_all(&temp_dir) {
+            log::warn!(
+                "[create_recording_zip] Failed to remove temp directory {}: {}",
+                temp_dir.display(),
+                e
+            );
+        }
+    }
+    result
+}
+
+// Location of a recording's archive in the exports folder
+fn recording_zip_path(app: &tauri::AppHandle, recording_id: &str) -> Result<PathBuf, String> {
+    Ok(storage::exports_dir(app)?.join(format!("{}.zip", recording_id)))
+}
+
+/// Upload archive of a recording, built in the exports folder
+#[derive(Serialize)]
+pub struct RecordingZip {
+    path: String,
+    size: u64,
+}
+
+/// Builds the upload archive of a recording in the exports folder. It stays there until
+/// `delete_recording_zip` removes it.
+pub async fn create_recording_zip(
+    app: tauri::AppHandle,
+    recording_id: String,
+) -> Result<RecordingZip, String> {
+    let zip_path = recording_zip_path(&app, &recording_id)?;
+    if let Some(parent) = zip_path.parent() {
+        create_dir_all(parent).map_err(|e| format!("Failed to create exports directory: {}", e))?;
+    }
+
+    // Built under another name so an interrupted export never looks complete
+    let partial_path = zip_path.with_extension("zip.partial");
+    write_recording_zip_or_clean_up(&app, &recording_id, &partial_path).await?;
+    fs::rename(&partial_path, &zip_path)
+        .map_err(|e| format!("Failed to move zip file into place: {}", e))?;
+
+    let size = fs::metadata(&zip_path)
+        .map_err(|e| format!("Failed to get zip file size: {}", e))?
+        .len();
+    Ok(RecordingZip {
+        path: zip_path.to_string_lossy().into_owned(),
+        size,
+    })
+}
+
+/// Up to `length` bytes of the recording's archive from `offset`, so uploads only ever hold
+/// one chunk of it in memory
+pub fn read_recording_zip_chunk(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    offset: u64,
+    length: u64,
+) -> Result<Vec<u8>, String> {
+    let mut file = File::open(recording_zip_path(app, recording_id)?)
+        .map_err(|e| format!("Failed to open zip file: {}", e))?;
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek in zip file: {}", e))?;
+
+    let mut chunk = Vec::new();
+    file.take(length)
+        .read_to_end(&mut chunk)
+        .map_err(|e| format!("Failed to read zip file: {}", e))?;
+    Ok(chunk)
+}
+
+pub async fn delete_recording_zip(
+    app: tauri::AppHandle,
+    recording_id: String,
+) -> Result<(), String> {
+    let zip_path = recording_zip_path(&app, &recording_id)?;
+    if zip_path.exists() {
+        fs::remove_file(&zip_path).map_err(|e| format!("Failed to remove zip file: {}", e))?;
+    }
+    Ok(())
 }
 
 pub async fn export_recording_zip(id: String, app: tauri::AppHandle) -> Result<String, String> {
-    let buf = create_recording_zip(app.clone(), id.clone()).await;
     let selected_dir = app.dialog().file().blocking_pick_folder();
 
     // If user cancels the dialog, selected_dir will be None
@@ -1213,8 +2386,8 @@ pub async fn export_recording_zip(id: String, app: tauri::AppHandle) -> Result<S
         let dir_path_str = dir_path.to_string();
         let file_path = Path::new(&dir_path_str).join(format!("export_recording_{}.zip", id));
 
-        // Write the buffer to the file
-        std::fs::write(&file_path, buf?).map_err(|e| format!("Failed to write zip file: {}", e))?;
+        // Built in place, as the chosen folder may be on another disk than the exports folder
+        write_recording_zip_or_clean_up(&app, &id, &file_path).await?;
 
         Ok(file_path.to_string_lossy().into_owned())
     } else {
@@ -1311,3 +2484,101 @@ fn create_overlay_window(app: &tauri::AppHandle, recorded_monitor :&DisplayInfo)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A session with one recorder in a fresh directory; the recorder steps are replaced by
+    // the tests, so no ffmpeg is started
+    fn session(name: &str) -> RecordingSession {
+        let dir = std::env::temp_dir().join(format!("record-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        let meta = serde_json::json!({
+            "id": name,
+            "timestamp": "20260101_000000",
+            "duration_seconds": 0,
+            "status": "recording",
+            "reason": null,
+            "title": "",
+            "description": "",
+            "platform": "linux",
+            "arch": "x86_64",
+            "version": "0.0.0",
+            "locale": "en",
+            "monitor": { "width": 640, "height": 480, "x": 0, "y": 0 },
+            "quest": null
+        });
+        fs::write(dir.join("meta.json"), meta.to_string()).unwrap();
+
+        let recorder = FFmpegRecorder::new_with_raw_input(
+            640,
+            480,
+            EncodingProfile::default(),
+            dir.join("recording.mp4"),
+        );
+        RecordingSession {
+            id: name.to_string(),
+            logger: Logger::new(dir.clone()).unwrap(),
+            dir,
+            started_at: Local::now(),
+            clock: Mutex::new(PauseClock::default()),
+            recorders: Mutex::new(vec![Recorder::FFmpeg(recorder)]),
+            listener: Mutex::new(None),
+        }
+    }
+
+    fn succeed(_: &mut Recorder) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn fail(_: &mut Recorder) -> Result<(), String> {
+        Err("recorder failed".to_string())
+    }
+
+    fn is_paused(session: &RecordingSession) -> bool {
+        session.clock.lock().unwrap().paused_at.is_some()
+    }
+
+    fn recorded_pauses(session: &RecordingSession) -> usize {
+        let meta: RecordingMeta = read_json_file(&session.dir.join("meta.json")).unwrap();
+        meta.pauses.len()
+    }
+
+    fn clean_up(session: RecordingSession) {
+        let dir = session.dir.clone();
+        session.logger.close().unwrap();
+        drop(session);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn failed_recorder_pause_leaves_the_session_resumable() {
+        let session = session("pause-fails");
+
+        assert_eq!(session.pause_with(fail), Err("recorder failed".to_string()));
+        assert!(is_paused(&session));
+
+        session.resume_with(succeed).unwrap();
+        assert!(!is_paused(&session));
+        assert_eq!(recorded_pauses(&session), 1);
+        clean_up(session);
+    }
+
+    #[test]
+    fn failed_recorder_resume_keeps_the_session_paused() {
+        let session = session("resume-fails");
+        session.pause_with(succeed).unwrap();
+
+        let resumed = session.resume_with(fail);
+        assert_eq!(resumed, Err("recorder failed".to_string()));
+        assert!(is_paused(&session));
+        assert_eq!(recorded_pauses(&session), 0);
+
+        session.resume_with(succeed).unwrap();
+        assert!(!is_paused(&session));
+        assert_eq!(recorded_pauses(&session), 1);
+        clean_up(session);
+    }
+}
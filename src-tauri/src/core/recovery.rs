@@ -0,0 +1,143 @@
+use crate::tools::ffmpeg::{init_ffmpeg, merge_segments};
+use crate::utils::storage;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Finds sessions left in "recording" by a crash, merges their video segments and
+/// marks them as recovered. Returns the IDs of the recovered sessions.
+pub fn recover_interrupted_recordings(app: &tauri::AppHandle) -> Result<Vec<String>, String> {
+    let recordings_dir = storage::recordings_dir(app)?;
+
+    if !recordings_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut interrupted = Vec::new();
+    for entry in fs::read_dir(&recordings_dir)
+        .map_err(|e| format!("Failed to read recordings directory: {}", e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let meta_path = entry.path().join("meta.json");
+        let Ok(meta_str) = fs::read_to_string(&meta_path) else {
+            continue;
+        };
+        let Ok(meta) = serde_json::from_str::<serde_json::Value>(&meta_str) else {
+            continue;
+        };
+        if meta.get("status").and_then(|s| s.as_str()) == Some("recording") {
+            interrupted.push((entry.path(), meta));
+        }
+    }
+
+    if interrupted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    log::info!(
+        "[Recovery] Found {} interrupted recording(s)",
+        interrupted.len()
+    );
+    init_ffmpeg()?;
+
+    let mut recovered = Vec::new();
+    for (session_dir, mut meta) in interrupted {
+        let id = meta
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| session_dir.file_name().unwrap().to_string_lossy().to_string());
+        log::info!("[Recovery] Recovering recording {}", id);
+
+        if let Err(e) = merge_session_segments(&session_dir) {
+            log::error!("[Recovery] Failed to merge video for {}: {}", id, e);
+            meta["reason"] = serde_json::json!(format!("recovery_failed: {}", e));
+        } else if meta.get("reason").map_or(true, |r| r.is_null()) {
+            meta["reason"] = serde_json::json!("crash");
+        }
+
+        meta["duration_seconds"] =
+            serde_json::json!(input_log_duration(&session_dir.join("input_log.jsonl")));
+        meta["status"] = serde_json::json!("recovered");
+
+        let meta_str = serde_json::to_string_pretty(&meta)
+            .map_err(|e| format!("Failed to serialize meta: {}", e))?;
+        fs::write(session_dir.join("meta.json"), meta_str)
+            .map_err(|e| format!("Failed to write meta file: {}", e))?;
+
+        recovered.push(id);
+    }
+
+    Ok(recovered)
+}
+
+// Groups `<stem>.part<N>.<ext>` files by their output name and merges each group in order
+fn merge_session_segments(session_dir: &Path) -> Result<(), String> {
+    let mut groups: BTreeMap<String, Vec<(u32, PathBuf)>> = BTreeMap::new();
+    for entry in
+        fs::read_dir(session_dir).map_err(|e| format!("Failed to read session directory: {}", e))?
+    {
+        let path = entry
+            .map_err(|e| format!("Failed to read directory entry: {}", e))?
+            .path();
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let Some((stem, rest)) = name.split_once(".part") else {
+            continue;
+        };
+        let Some((index, extension)) = rest.split_once('.') else {
+            continue;
+        };
+        let Ok(index) = index.parse::<u32>() else {
+            continue;
+        };
+        // ffmpeg leaves an empty file behind when it dies before the first fragment
+        if path.metadata().map(|m| m.len() == 0).unwrap_or(true) {
+            let _ = fs::remove_file(&path);
+            continue;
+        }
+        groups
+            .entry(format!("{}.{}", stem, extension))
+            .or_default()
+            .push((index, path));
+    }
+
+    for (output_name, mut parts) in groups {
+        parts.sort_by_key(|(index, _)| *index);
+        let segments: Vec<PathBuf> = parts.into_iter().map(|(_, path)| path).collect();
+        log::info!(
+            "[Recovery] Merging {} segment(s) into {}",
+            segments.len(),
+            output_name
+        );
+        merge_segments(&segments, &session_dir.join(output_name))?;
+    }
+
+    Ok(())
+}
+
+// The input log is on the video timeline, so its first and last events bound the duration
+fn input_log_duration(log_path: &Path) -> u64 {
+    let Ok(file) = File::open(log_path) else {
+        return 0;
+    };
+
+    let mut first = None;
+    let mut last = None;
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        if let Some(time) = event.get("time").and_then(|t| t.as_i64()) {
+            first.get_or_insert(time);
+            last = Some(time);
+        }
+    }
+
+    match (first, last) {
+        (Some(first), Some(last)) => ((last - first).max(0) / 1000) as u64,
+        _ => 0,
+    }
+}
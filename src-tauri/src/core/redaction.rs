@@ -0,0 +1,381 @@
+use crate::core::input_log::InputEvent;
+use crate::utils::settings::RedactionSettings;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+// Roles and subroles of password inputs across AT-SPI, AX and UI Automation, compared
+// lowercase without spaces or underscores
+const SECURE_ROLES: &[&str] = &[
+    "passwordtext",
+    "password",
+    "securetextfield",
+    "axsecuretextfield",
+];
+
+/// What scrubbed text is replaced with
+pub const REDACTED: &str = "[redacted]";
+
+/// Axtree keys describing the structure of the tree rather than what it shows
+pub const STRUCTURAL_KEYS: &[&str] = &["role", "subrole", "states", "bbox"];
+
+/// Why typing is currently being redacted
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sensitive {
+    SecureField,
+    // The denylist entry that matched
+    App(String),
+}
+
+impl Sensitive {
+    pub fn reason(&self) -> String {
+        match self {
+            Sensitive::SecureField => "secure_field".to_string(),
+            Sensitive::App(app) => format!("denylisted_app:{}", app),
+        }
+    }
+}
+
+/// How a private region is hidden in the exported video
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionStyle {
+    #[default]
+    Blur,
+    Pixelate,
+}
+
+/// Part of the frame hidden during a private range, in pixels of the recorded area (the
+/// space input_log.jsonl positions are in). `monitor` picks one of the recorded monitors'
+/// videos; without it the region applies to every video.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PrivateRegion {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<usize>,
+    #[serde(default)]
+    pub style: RegionStyle,
+}
+
+/// Screen area of an accessibility node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Bounds {
+    fn of(node: &Value) -> Option<Self> {
+        let bbox = node.get("bbox")?;
+        let get = |key: &str| bbox.get(key).and_then(Value::as_f64);
+        let bounds = Bounds {
+            x: get("x")?,
+            y: get("y")?,
+            width: get("width")?,
+            height: get("height")?,
+        };
+        (bounds.width > 0.0 && bounds.height > 0.0).then_some(bounds)
+    }
+
+    fn union(self, other: Bounds) -> Bounds {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Bounds {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+/// A finished redacted span, on the video timeline
+#[derive(Debug)]
+pub struct Span {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub sensitive: Sensitive,
+    // Screen area of the secure field, when it was known for every snapshot of the span
+    pub bounds: Option<Bounds>,
+}
+
+// The dump-tree output differs per platform: focus is either a `focused` flag or an entry
+// in a `states` list
+fn is_focused(node: &Value) -> bool {
+    node.get("focused")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+        || node
+            .get("states")
+            .and_then(Value::as_array)
+            .is_some_and(|states| states.iter().any(|state| state.as_str() == Some("focused")))
+}
+
+fn is_secure(node: &Value) -> bool {
+    let role_matches = ["role", "subrole"].iter().any(|key| {
+        node.get(*key).and_then(Value::as_str).is_some_and(|role| {
+            let role = role.to_lowercase().replace([' ', '_'], "");
+            SECURE_ROLES.contains(&role.as_str())
+        })
+    });
+    role_matches
+        || ["is_password", "isPassword", "protected"]
+            .iter()
+            .any(|key| node.get(*key).and_then(Value::as_bool).unwrap_or(false))
+}
+
+fn children(node: &Value) -> &[Value] {
+    node.get("children")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+// Fills `path` with the nodes from the top level down to the focused one
+fn find_focused<'a>(nodes: &'a [Value], path: &mut Vec<&'a Value>) -> bool {
+    for node in nodes {
+        path.push(node);
+        if is_focused(node) || find_focused(children(node), path) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+// Nodes from the top level down to the focused one. When the snapshot has no focus
+// information, a single top-level node is taken to be the focused app.
+fn focus_path(snapshot: &Value) -> Vec<&Value> {
+    let tree = snapshot.get("tree").unwrap_or(snapshot);
+    let nodes = match tree {
+        Value::Array(nodes) => nodes.as_slice(),
+        node => std::slice::from_ref(node),
+    };
+
+    let mut path = Vec::new();
+    if !find_focused(nodes, &mut path) {
+        if let [app] = nodes {
+            path.push(app);
+        }
+    }
+    path
+}
+
+/// Checks an axtree snapshot (the `data` of an `axtree` event) for a focused password
+/// field or a focused denylisted application. Apps are matched, case-insensitively, against
+/// the names on the way to the focused node, which covers the app, its window title and
+/// (in browsers) the page title.
+pub fn detect(snapshot: &Value, settings: &RedactionSettings) -> Option<Sensitive> {
+    if !settings.enabled {
+        return None;
+    }
+
+    let path = focus_path(snapshot);
+    if settings.secure_fields
+        && path
+            .last()
+            .is_some_and(|node| is_focused(node) && is_secure(node))
+    {
+        return Some(Sensitive::SecureField);
+    }
+
+    let names: Vec<String> = path
+        .iter()
+        .filter_map(|node| node.get("name").and_then(Value::as_str))
+        .map(str::to_lowercase)
+        .collect();
+    settings
+        .denylisted_apps
+        .iter()
+        .find(|app| {
+            let app = app.to_lowercase();
+            names.iter().any(|name| name.contains(&app))
+        })
+        .map(|app| Sensitive::App(app.clone()))
+}
+
+/// Tracks redaction across snapshots. Snapshots only arrive every few seconds, so a
+/// redacted span is taken to begin at the last snapshot that was still clean.
+pub struct Redactor {
+    settings: RedactionSettings,
+    active: Option<Sensitive>,
+    // Video offset where the current span starts
+    span_start_ms: i64,
+    last_snapshot_ms: i64,
+    // Whether the current span already has its `redacted_keys` marker
+    marked: bool,
+    // Area covered by the secure field so far in the current span
+    bounds: Option<Bounds>,
+}
+
+impl Redactor {
+    pub fn new(settings: RedactionSettings) -> Self {
+        Self {
+            settings,
+            active: None,
+            span_start_ms: 0,
+            last_snapshot_ms: 0,
+            marked: false,
+            bounds: None,
+        }
+    }
+
+    pub fn active(&self) -> Option<&Sensitive> {
+        self.active.as_ref()
+    }
+
+    /// Takes a snapshot logged at `offset_ms` and returns the span it closed, if any
+    pub fn observe(&mut self, snapshot: &Value, offset_ms: i64) -> Option<Span> {
+        let detected = detect(snapshot, &self.settings);
+        let previous_snapshot_ms = std::mem::replace(&mut self.last_snapshot_ms, offset_ms);
+        // The field may move or resize, so the span covers everywhere it has been
+        let bounds = match detected {
+            Some(Sensitive::SecureField) => focus_path(snapshot).last().and_then(|n| Bounds::of(n)),
+            _ => None,
+        };
+        if detected == self.active {
+            self.bounds = self.bounds.zip(bounds).map(|(a, b)| a.union(b));
+            return None;
+        }
+
+        let closed = self.finish(offset_ms);
+        self.bounds = bounds;
+        if let Some(sensitive) = &detected {
+            log::info!("[Redaction] Redacting input: {}", sensitive.reason());
+            // A new span right after another one continues from where it ended
+            self.span_start_ms = match &closed {
+                Some(_) => offset_ms,
+                None => previous_snapshot_ms,
+            };
+        }
+        self.active = detected;
+        closed
+    }
+
+    /// Returns true the first time it's called in a span, when the marker is due
+    pub fn take_marker(&mut self) -> bool {
+        !std::mem::replace(&mut self.marked, true)
+    }
+
+    /// Ends the current span at `offset_ms`
+    pub fn finish(&mut self, offset_ms: i64) -> Option<Span> {
+        let sensitive = self.active.take()?;
+        self.marked = false;
+        log::info!("[Redaction] Input no longer redacted");
+        Some(Span {
+            start_ms: self.span_start_ms,
+            end_ms: offset_ms,
+            sensitive,
+            bounds: self.bounds.take(),
+        })
+    }
+}
+
+// Replaces every string in the snapshot except structural ones, keeping numbers and flags
+fn scrub_snapshot(value: &mut Value) -> bool {
+    match value {
+        Value::String(text) if !text.is_empty() && text != REDACTED => {
+            *text = REDACTED.to_string();
+            true
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .fold(false, |scrubbed, item| scrub_snapshot(item) || scrubbed),
+        Value::Object(fields) => fields
+            .iter_mut()
+            .filter(|(key, _)| !STRUCTURAL_KEYS.contains(&key.as_str()))
+            .fold(false, |scrubbed, (_, field)| {
+                scrub_snapshot(field) || scrubbed
+            }),
+        _ => false,
+    }
+}
+
+/// Blanks out what an event says while keeping its type and timing, for events inside
+/// private ranges. Returns false when there was nothing to scrub.
+pub fn scrub_event(event: &mut InputEvent) -> bool {
+    match event {
+        InputEvent::Keydown {
+            key,
+            modifiers,
+            scancode,
+            text,
+        } => {
+            *key = REDACTED.to_string();
+            modifiers.clear();
+            *scancode = None;
+            if text.is_some() {
+                *text = Some(REDACTED.to_string());
+            }
+            true
+        }
+        InputEvent::Keyup {
+            key,
+            modifiers,
+            scancode,
+        } => {
+            *key = REDACTED.to_string();
+            modifiers.clear();
+            *scancode = None;
+            true
+        }
+        InputEvent::Shortcut {
+            keys,
+            modifiers,
+            key,
+        } => {
+            *keys = REDACTED.to_string();
+            modifiers.clear();
+            *key = REDACTED.to_string();
+            true
+        }
+        InputEvent::Axtree(snapshot) => scrub_snapshot(snapshot),
+        _ => false,
+    }
+}
+
+/// Adds a span to the session's private_ranges.json, which `create_recording_zip` removes
+/// from the input log and hides in the video: only `regions` when there are any, the whole
+/// frame otherwise
+pub fn append_private_range(
+    session_dir: &Path,
+    start_ms: i64,
+    end_ms: i64,
+    reason: &str,
+    regions: &[PrivateRegion],
+) -> Result<(), String> {
+    let path = session_dir.join("private_ranges.json");
+    let mut ranges: Vec<Value> = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse private ranges: {}", e))?,
+        Err(_) => Vec::new(),
+    };
+    let mut range = serde_json::json!({
+        "start": start_ms,
+        "end": end_ms,
+        "count": 1,
+        "reason": reason
+    });
+    if !regions.is_empty() {
+        range["regions"] = serde_json::to_value(regions)
+            .map_err(|e| format!("Failed to serialize private regions: {}", e))?;
+    }
+    ranges.push(range);
+
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(&ranges)
+            .map_err(|e| format!("Failed to serialize private ranges: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to write private ranges: {}", e))
+}
@@ -0,0 +1,223 @@
+use crate::core::limits::dir_size;
+use crate::utils::settings::{RetentionPolicy, Settings};
+use crate::utils::storage::{recording_dir, recordings_dir};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// A recording the retention policy would delete, and which rule selected it
+#[derive(Serialize, Clone, Debug)]
+pub struct RetentionCandidate {
+    pub id: String,
+    pub title: String,
+    pub timestamp: String,
+    pub size_bytes: u64,
+    // "uploaded", "keep_last", "max_age" or "quota"
+    pub reason: String,
+}
+
+// The parts of meta.json the policy looks at
+#[derive(Deserialize)]
+struct StoredRecording {
+    id: String,
+    timestamp: String,
+    status: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    uploaded_at: Option<String>,
+}
+
+struct Entry {
+    recording: StoredRecording,
+    path: PathBuf,
+    recorded_at: Option<DateTime<Local>>,
+    size_bytes: u64,
+}
+
+// Newest first; folders without a readable meta.json are left alone
+fn load_entries(recordings_dir: &Path) -> Result<Vec<Entry>, String> {
+    if !recordings_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(recordings_dir)
+        .map_err(|e| format!("Failed to read recordings directory: {}", e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let Ok(meta_str) = fs::read_to_string(entry.path().join("meta.json")) else {
+            continue;
+        };
+        let Ok(recording) = serde_json::from_str::<StoredRecording>(&meta_str) else {
+            continue;
+        };
+        entries.push(Entry {
+            recorded_at: DateTime::parse_from_rfc3339(&recording.timestamp)
+                .ok()
+                .map(|t| t.with_timezone(&Local)),
+            size_bytes: dir_size(&entry.path()),
+            path: entry.path(),
+            recording,
+        });
+    }
+
+    entries.sort_by(|a, b| b.recording.timestamp.cmp(&a.recording.timestamp));
+    Ok(entries)
+}
+
+// Pinned recordings and sessions still marked "recording" (the active one, or a crashed one
+// waiting for recovery) are never selected and do not count towards "keep last N"
+fn select_candidates(entries: &[Entry], policy: &RetentionPolicy) -> Vec<(usize, String)> {
+    let now = Local::now();
+    let mut selected: Vec<(usize, String)> = Vec::new();
+    let mut kept = 0;
+
+    for (index, entry) in entries.iter().enumerate() {
+        let recording = &entry.recording;
+        if recording.pinned || recording.status == "recording" {
+            continue;
+        }
+
+        let expired = match (policy.max_age_days, entry.recorded_at) {
+            (Some(days), Some(recorded_at)) => now - recorded_at > Duration::days(days as i64),
+            _ => false,
+        };
+
+        let reason = if policy.delete_after_upload && recording.uploaded_at.is_some() {
+            Some("uploaded")
+        } else if expired {
+            Some("max_age")
+        } else if policy.keep_last.is_some_and(|keep_last| kept >= keep_last) {
+            Some("keep_last")
+        } else {
+            kept += 1;
+            None
+        };
+
+        if let Some(reason) = reason {
+            selected.push((index, reason.to_string()));
+        }
+    }
+
+    // Quota: drop the oldest remaining recordings until everything fits
+    if let Some(max_total_gb) = policy.max_total_gb {
+        let quota = (max_total_gb * GB) as u64;
+        let mut total: u64 = entries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !selected.iter().any(|(i, _)| i == index))
+            .map(|(_, entry)| entry.size_bytes)
+            .sum();
+
+        for (index, entry) in entries.iter().enumerate().rev() {
+            if total <= quota {
+                break;
+            }
+            let recording = &entry.recording;
+            if recording.pinned
+                || recording.status == "recording"
+                || selected.iter().any(|(i, _)| *i == index)
+            {
+                continue;
+            }
+            total -= entry.size_bytes;
+            selected.push((index, "quota".to_string()));
+        }
+    }
+
+    selected
+}
+
+fn plan(app: &tauri::AppHandle) -> Result<(Vec<Entry>, Vec<RetentionCandidate>), String> {
+    let policy = Settings::load(app).retention;
+    let entries = load_entries(&recordings_dir(app)?)?;
+    let candidates = select_candidates(&entries, &policy)
+        .into_iter()
+        .map(|(index, reason)| {
+            let entry = &entries[index];
+            RetentionCandidate {
+                id: entry.recording.id.clone(),
+                title: entry.recording.title.clone(),
+                timestamp: entry.recording.timestamp.clone(),
+                size_bytes: entry.size_bytes,
+                reason,
+            }
+        })
+        .collect();
+    Ok((entries, candidates))
+}
+
+/// Lists what `enforce_retention` would delete right now, without deleting anything
+pub fn preview_retention(app: &tauri::AppHandle) -> Result<Vec<RetentionCandidate>, String> {
+    plan(app).map(|(_, candidates)| candidates)
+}
+
+/// Deletes the recordings selected by the retention policy and returns them
+pub fn enforce_retention(app: &tauri::AppHandle) -> Result<Vec<RetentionCandidate>, String> {
+    let (entries, candidates) = plan(app)?;
+
+    let mut deleted = Vec::new();
+    for candidate in candidates {
+        let Some(entry) = entries.iter().find(|e| e.recording.id == candidate.id) else {
+            continue;
+        };
+        log::info!(
+            "[Retention] Deleting recording {} ({})",
+            candidate.id,
+            candidate.reason
+        );
+        match fs::remove_dir_all(&entry.path) {
+            Ok(()) => deleted.push(candidate),
+            Err(e) => log::error!("[Retention] Failed to delete {}: {}", candidate.id, e),
+        }
+    }
+
+    Ok(deleted)
+}
+
+// Edits a single meta.json field without touching the rest of the file
+fn update_meta(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    let meta_path = recording_dir(app, recording_id)?.join("meta.json");
+    let meta_str =
+        fs::read_to_string(&meta_path).map_err(|e| format!("Failed to read meta file: {}", e))?;
+    let mut meta: serde_json::Value =
+        serde_json::from_str(&meta_str).map_err(|e| format!("Failed to parse meta file: {}", e))?;
+    meta[key] = value;
+
+    fs::write(
+        &meta_path,
+        serde_json::to_string_pretty(&meta)
+            .map_err(|e| format!("Failed to serialize meta: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to write meta file: {}", e))
+}
+
+/// Pinned recordings are never deleted by the retention policy
+pub fn set_recording_pinned(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    pinned: bool,
+) -> Result<(), String> {
+    update_meta(app, recording_id, "pinned", serde_json::json!(pinned))
+}
+
+/// Records a successful upload, which `delete_after_upload` relies on
+pub fn mark_recording_uploaded(app: &tauri::AppHandle, recording_id: &str) -> Result<(), String> {
+    update_meta(
+        app,
+        recording_id,
+        "uploaded_at",
+        serde_json::json!(Local::now().to_rfc3339()),
+    )
+}
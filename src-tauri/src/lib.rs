@@ -6,23 +6,33 @@ mod core;
 mod tools;
 mod utils;
 
+use core::recovery::recover_interrupted_recordings;
+use core::retention::enforce_retention;
 use core::record::{set_rec_state, QuestState};
 #[cfg(target_os = "macos")]
 use utils::permissions::{has_ax_perms, has_record_perms, request_ax_perms, request_record_perms};
 
 use crate::commands::general::{greet, list_apps, take_screenshot, capture_all_monitors};
 use crate::commands::record::{
-    create_recording_zip, delete_recording, export_recording_zip, get_app_data_dir,
-    get_current_quest, get_recording_file, get_recording_state, list_recordings,
-    open_recording_folder, process_recording, start_recording, stop_recording, write_file,
-    write_recording_file,
+    create_recording_zip_file, delete_recording, delete_recording_zip, export_recording_zip,
+    get_app_data_dir, get_current_quest, get_recording_file, get_recording_state, list_recordings,
+    mark_recording_uploaded, open_recording_folder, pause_recording, preview_retention,
+    process_recording, read_recording_zip_chunk, resume_recording, set_recording_pinned,
+    start_recording, stop_recording, write_file, write_recording_file,
+};
+use crate::commands::recordings::{
+    export_recordings, get_input_log_schema, get_recordings_dir, move_recordings,
 };
-use crate::commands::recordings::export_recordings;
 use crate::commands::settings::{
-    get_onboarding_complete, get_upload_data_allowed, set_onboarding_complete,
+    get_available_codecs, get_encoding_profile, get_input_sampling, get_onboarding_complete,
+    get_pii_scrubbing, get_recording_limits, get_redaction_settings, get_retention_policy,
+    get_upload_data_allowed, set_encoding_profile, set_input_sampling, set_onboarding_complete,
+    set_pii_scrubbing, set_recording_limits, set_redaction_settings, set_retention_policy,
     set_upload_data_allowed,
 };
-use crate::commands::tools::{check_tools, init_tools};
+use crate::commands::tools::{
+    check_tools, get_ffmpeg_capabilities, init_tools, list_audio_devices,
+};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -57,6 +67,8 @@ pub fn run() {
             greet,
             start_recording,
             stop_recording,
+            pause_recording,
+            resume_recording,
             take_screenshot,
             list_apps,
             capture_all_monitors,
@@ -74,17 +86,40 @@ pub fn run() {
             set_onboarding_complete,
             init_tools,
             check_tools,
+            get_ffmpeg_capabilities,
+            list_audio_devices,
             get_app_data_dir,
             write_file,
             write_recording_file,
             open_recording_folder,
             process_recording,
-            create_recording_zip,
+            create_recording_zip_file,
+            read_recording_zip_chunk,
+            delete_recording_zip,
             export_recording_zip,
             get_upload_data_allowed,
             set_upload_data_allowed,
+            get_encoding_profile,
+            set_encoding_profile,
+            get_available_codecs,
+            get_recording_limits,
+            set_recording_limits,
+            get_retention_policy,
+            set_retention_policy,
+            get_input_sampling,
+            set_input_sampling,
+            get_redaction_settings,
+            set_redaction_settings,
+            get_pii_scrubbing,
+            set_pii_scrubbing,
             export_recordings,
+            get_recordings_dir,
+            move_recordings,
+            get_input_log_schema,
             delete_recording,
+            set_recording_pinned,
+            mark_recording_uploaded,
+            preview_retention,
             get_recording_state,
             get_current_quest,
         ])
@@ -104,6 +139,27 @@ pub fn run() {
             // Emit initial recording status
             set_rec_state(&app.handle(), "off".to_string(), None)?;
 
+            // Repair sessions a crash left in "recording" without blocking startup, then
+            // apply the retention policy once they are no longer marked as in progress
+            let recovery_handle = app.handle().clone();
+            std::thread::spawn(move || {
+                match recover_interrupted_recordings(&recovery_handle) {
+                    Ok(ids) if !ids.is_empty() => {
+                        log::info!("[Recovery] Recovered recordings: {:?}", ids)
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::error!("[Recovery] Failed to recover recordings: {}", e),
+                }
+
+                match enforce_retention(&recovery_handle) {
+                    Ok(deleted) if !deleted.is_empty() => {
+                        log::info!("[Retention] Deleted {} recording(s)", deleted.len())
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::error!("[Retention] Failed to apply retention policy: {}", e),
+                }
+            });
+
             // Set up window close handler after all other operations
             let window_handle = window.clone();
             window.on_window_event(move |event| {
@@ -0,0 +1,279 @@
+#[cfg(target_os = "linux")]
+use crate::tools::ffmpeg::probe_capabilities;
+#[cfg(not(target_os = "linux"))]
+use crate::tools::ffmpeg::FFMPEG_PATH;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Audio sources recorded along with the screen, chosen per recording
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AudioCapture {
+    pub microphone: bool,
+    pub system_audio: bool,
+    // Name from `list_audio_devices`; the default input is used when unset
+    pub microphone_device: Option<String>,
+}
+
+impl AudioCapture {
+    pub fn is_enabled(&self) -> bool {
+        self.microphone || self.system_audio
+    }
+}
+
+/// An ffmpeg audio input, e.g. `-f pulse -i default`
+#[derive(Clone, Debug)]
+pub struct AudioInput {
+    pub format: String,
+    pub device: String,
+}
+
+/// Resolves the requested sources to ffmpeg inputs, failing with a clear message when
+/// this machine has no way to capture one of them
+pub fn resolve_audio_inputs(capture: &AudioCapture) -> Result<Vec<AudioInput>, String> {
+    let mut inputs = Vec::new();
+    if capture.microphone {
+        inputs.push(microphone_input(capture.microphone_device.as_deref())?);
+    }
+    if capture.system_audio {
+        inputs.push(system_audio_input()?);
+    }
+
+    for input in &inputs {
+        log::info!("[Audio] Using {} input {}", input.format, input.device);
+    }
+    Ok(inputs)
+}
+
+#[cfg(target_os = "linux")]
+fn microphone_input(device: Option<&str>) -> Result<AudioInput, String> {
+    let capabilities = probe_capabilities()?;
+    let format = ["pulse", "alsa"]
+        .into_iter()
+        .find(|format| capabilities.has_input_device(format))
+        .ok_or_else(|| {
+            "The installed FFmpeg build supports neither PulseAudio nor ALSA input".to_string()
+        })?;
+
+    Ok(AudioInput {
+        format: format.to_string(),
+        device: device.unwrap_or("default").to_string(),
+    })
+}
+
+// PulseAudio (and PipeWire's pulse server) expose what is playing as the sink's monitor
+#[cfg(target_os = "linux")]
+fn system_audio_input() -> Result<AudioInput, String> {
+    if !probe_capabilities()?.has_input_device("pulse") {
+        return Err(
+            "System audio capture needs an FFmpeg build with PulseAudio input".to_string(),
+        );
+    }
+
+    Ok(AudioInput {
+        format: "pulse".to_string(),
+        device: "@DEFAULT_MONITOR@".to_string(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn microphone_input(device: Option<&str>) -> Result<AudioInput, String> {
+    let name = match device {
+        Some(device) => device.to_string(),
+        None => list_audio_devices()?
+            .into_iter()
+            .find(|name| !is_loopback_device(name))
+            .ok_or_else(|| "No microphone found".to_string())?,
+    };
+
+    Ok(AudioInput {
+        format: "dshow".to_string(),
+        device: format!("audio={}", name),
+    })
+}
+
+// DirectShow has no loopback of its own, so look for "Stereo Mix" or a virtual cable
+#[cfg(target_os = "windows")]
+fn system_audio_input() -> Result<AudioInput, String> {
+    let name = list_audio_devices()?
+        .into_iter()
+        .find(|name| is_loopback_device(name))
+        .ok_or_else(|| {
+            "System audio capture needs a loopback device such as Stereo Mix or VB-CABLE"
+                .to_string()
+        })?;
+
+    Ok(AudioInput {
+        format: "dshow".to_string(),
+        device: format!("audio={}", name),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn microphone_input(device: Option<&str>) -> Result<AudioInput, String> {
+    let devices = list_audio_devices()?;
+    let index = match device {
+        Some(device) => devices
+            .iter()
+            .position(|name| name == device)
+            .ok_or_else(|| format!("Audio device '{}' not found", device))?,
+        None => devices
+            .iter()
+            .position(|name| !is_loopback_device(name))
+            .ok_or_else(|| "No microphone found".to_string())?,
+    };
+
+    Ok(AudioInput {
+        format: "avfoundation".to_string(),
+        device: format!(":{}", index),
+    })
+}
+
+// macOS cannot record its output without a virtual device such as BlackHole
+#[cfg(target_os = "macos")]
+fn system_audio_input() -> Result<AudioInput, String> {
+    let index = list_audio_devices()?
+        .iter()
+        .position(|name| is_loopback_device(name))
+        .ok_or_else(|| {
+            "System audio capture needs a loopback device such as BlackHole".to_string()
+        })?;
+
+    Ok(AudioInput {
+        format: "avfoundation".to_string(),
+        device: format!(":{}", index),
+    })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn microphone_input(_device: Option<&str>) -> Result<AudioInput, String> {
+    Err("Unsupported platform".to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn system_audio_input() -> Result<AudioInput, String> {
+    Err("Unsupported platform".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_loopback_device(name: &str) -> bool {
+    const LOOPBACK_DEVICES: &[&str] = &[
+        "stereo mix",
+        "virtual-audio-capturer",
+        "cable output",
+        "blackhole",
+        "soundflower",
+        "loopback",
+    ];
+    let name = name.to_lowercase();
+    LOOPBACK_DEVICES.iter().any(|device| name.contains(device))
+}
+
+/// Lists the names of the audio capture devices ffmpeg can open on this machine
+pub fn list_audio_devices() -> Result<Vec<String>, String> {
+    #[cfg(target_os = "linux")]
+    {
+        // ffmpeg's pulse input cannot list sources, so ask the pulse server directly
+        let mut devices = vec!["default".to_string()];
+        if let Ok(output) = Command::new("pactl")
+            .args(["list", "short", "sources"])
+            .output()
+        {
+            // Rows look like "53  alsa_input.pci-0000_00_1f.3.analog-stereo  PipeWire  s32le ..."
+            let sources = String::from_utf8_lossy(&output.stdout);
+            devices.extend(
+                sources
+                    .lines()
+                    .filter_map(|line| line.split_whitespace().nth(1))
+                    .filter(|name| !name.ends_with(".monitor"))
+                    .map(|name| name.to_string()),
+            );
+        }
+        Ok(devices)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let ffmpeg = FFMPEG_PATH
+            .get()
+            .ok_or_else(|| "FFmpeg not initialized".to_string())?;
+
+        let mut command = Command::new(ffmpeg);
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            command.creation_flags(0x08000000); // CREATE_NO_WINDOW constant
+            command.args([
+                "-hide_banner",
+                "-list_devices",
+                "true",
+                "-f",
+                "dshow",
+                "-i",
+                "dummy",
+            ]);
+        }
+        #[cfg(target_os = "macos")]
+        command.args([
+            "-hide_banner",
+            "-f",
+            "avfoundation",
+            "-list_devices",
+            "true",
+            "-i",
+            "",
+        ]);
+
+        // The listing is printed to stderr and ffmpeg exits with an error afterwards
+        let output = command
+            .output()
+            .map_err(|e| format!("Failed to list audio devices: {}", e))?;
+        Ok(parse_device_list(&String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+// dshow prints `"Name" (audio)` (or `"Name"` below "DirectShow audio devices" in older
+// builds); avfoundation prints `[0] Name` below "AVFoundation audio devices:"
+#[cfg(not(target_os = "linux"))]
+fn parse_device_list(output: &str) -> Vec<String> {
+    let mut devices = Vec::new();
+    let mut in_audio_section = false;
+
+    for line in output.lines() {
+        if line.contains("audio devices") {
+            in_audio_section = true;
+            continue;
+        }
+        if line.contains("video devices") {
+            in_audio_section = false;
+            continue;
+        }
+        if line.contains("Alternative name") {
+            continue;
+        }
+
+        // Drop the "[dshow @ 0x...]" / "[AVFoundation indev @ 0x...]" prefix
+        let entry = line
+            .split_once("] ")
+            .map(|(_, rest)| rest.trim())
+            .unwrap_or(line.trim());
+
+        if let Some((name, kind)) = entry
+            .strip_prefix('"')
+            .and_then(|rest| rest.split_once('"'))
+        {
+            if kind.contains("(audio)") || (in_audio_section && !kind.contains("(video)")) {
+                devices.push(name.to_string());
+            }
+        } else if in_audio_section {
+            if let Some((_, name)) = entry
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once("] "))
+            {
+                devices.push(name.trim().to_string());
+            }
+        }
+    }
+
+    devices
+}
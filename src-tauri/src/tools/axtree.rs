@@ -1,15 +1,20 @@
+use crate::core::input_log::InputEvent;
+use crate::core::pii::PiiScrubber;
 use crate::utils::github_release;
+use crate::utils::settings::Settings;
 use log::info;
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 use std::time::Duration;
 
 static DUMP_TREE_PATH: OnceLock<PathBuf> = OnceLock::new();
 static POLLING_ACTIVE: OnceLock<Arc<Mutex<bool>>> = OnceLock::new();
+static POLLING_PAUSED: AtomicBool = AtomicBool::new(false);
 
 #[cfg(target_os = "windows")]
 const DUMP_TREE_URL: &str = "https://github.com/omniminds-ai/vm-ax-tree-parsers/releases/latest/download/dump-tree-windows-x64.exe";
@@ -63,22 +68,29 @@ pub fn init_dump_tree() -> Result<(), String> {
     Ok(())
 }
 
-pub fn start_dump_tree_polling(_: tauri::AppHandle) -> Result<(), String> {
+pub fn start_dump_tree_polling(app: tauri::AppHandle) -> Result<(), String> {
     let dump_tree = DUMP_TREE_PATH
         .get()
         .ok_or_else(|| "dump-tree not initialized".to_string())?
         .clone();
+    let scrubber = Arc::new(PiiScrubber::new(&Settings::load(&app).pii_scrubbing)?);
 
     let polling_active = POLLING_ACTIVE
         .get()
         .ok_or_else(|| "Polling state not initialized".to_string())?;
     *polling_active.lock().unwrap() = true;
+    POLLING_PAUSED.store(false, Ordering::SeqCst);
 
     info!("[AxTree] Starting dump-tree polling");
 
     thread::spawn(move || {
         info!("[AxTree] Polling thread started");
         while *POLLING_ACTIVE.get().unwrap().lock().unwrap() {
+            if POLLING_PAUSED.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(250));
+                continue;
+            }
+
             info!("[AxTree] Starting new dump-tree process");
 
             // Run dump-tree and capture output
@@ -98,19 +110,27 @@ pub fn start_dump_tree_polling(_: tauri::AppHandle) -> Result<(), String> {
                 Ok(mut child) => {
                     // Create separate threads for handling stdout and stderr
                     let stdout_thread = if let Some(stdout) = child.stdout.take() {
+                        let scrubber = scrubber.clone();
                         let stdout_handle = thread::spawn(move || {
                             let reader = BufReader::new(stdout);
                             for line in reader.lines() {
                                 if let Ok(line) = line {
+                                    // Drop snapshots taken while the recording is paused
+                                    if POLLING_PAUSED.load(Ordering::SeqCst) {
+                                        continue;
+                                    }
                                     // info!("[AxTree] STDOUT line: {}", line);
                                     // Try to parse as JSON
                                     if let Ok(mut json) = serde_json::from_str::<Value>(&line) {
-                                        // Modify the event field
-                                        if let Some(obj) = json.as_object_mut() {
-                                            obj.insert("event".to_string(), json!("axtree"));
-                                            // Log the modified event
-                                            let _ = crate::core::record::log_input(json!(obj));
-                                        }
+                                        // Snapshots carry the tree in `data`
+                                        let mut data = match json.get_mut("data") {
+                                            Some(data) => data.take(),
+                                            None => json,
+                                        };
+                                        // Personal data never reaches the log
+                                        scrubber.scrub_snapshot(&mut data);
+                                        let event = InputEvent::Axtree(data);
+                                        let _ = crate::core::record::log_input(event);
                                     }
                                 }
                             }
@@ -178,3 +198,15 @@ pub fn stop_dump_tree_polling() -> Result<(), String> {
     }
     Ok(())
 }
+
+pub fn pause_dump_tree_polling() -> Result<(), String> {
+    info!("[AxTree] Pausing dump-tree polling");
+    POLLING_PAUSED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+pub fn resume_dump_tree_polling() -> Result<(), String> {
+    info!("[AxTree] Resuming dump-tree polling");
+    POLLING_PAUSED.store(false, Ordering::SeqCst);
+    Ok(())
+}
@@ -1,15 +1,22 @@
+use crate::tools::audio::AudioInput;
 use crate::utils::downloader::download_file;
 use crate::core::archive;
+use crate::utils::settings::{EncodingProfile, SUPPORTED_CODECS};
+use serde::Serialize;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::Instant;
 
 // #[cfg(not(target_os = "macos"))]
 use {std::io::Write, std::process::Stdio, std::thread, std::time::Duration};
 
 pub static FFMPEG_PATH: OnceLock<PathBuf> = OnceLock::new();
 pub static FFPROBE_PATH: OnceLock<PathBuf> = OnceLock::new();
+// Held while a binary is looked up or downloaded, so startup and the frontend can both
+// initialize without racing on the paths or the shared archive
+static INIT_LOCK: Mutex<()> = Mutex::new(());
 
 const FFMPEG_URLS: &[(&str, &str)] = &[
     ("windows", "https://www.gyan.dev/ffmpeg/builds/ffmpeg-release-essentials.zip"),
@@ -18,6 +25,9 @@ const FFMPEG_URLS: &[(&str, &str)] = &[
 
 ];
 
+// A running segment that has not encoded a frame for this long is considered stalled
+const STALL_TIMEOUT: Duration = Duration::from_secs(15);
+
 #[cfg(target_os = "macos")]
 const FFPROBE_MACOS: &str = "https://www.osxexperts.net/ffprobe71intel.zip";
 
@@ -266,6 +276,7 @@ pub fn init_ffmpeg_and_ffprobe() -> Result<(), String> {
 }
 
 pub fn init_ffmpeg() -> Result<(), String> {
+    let _init = INIT_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
     if FFMPEG_PATH.get().is_some() {
         log::info!("[FFmpeg] FFmpeg already initialized");
         return Ok(());
@@ -282,7 +293,7 @@ pub fn init_ffmpeg() -> Result<(), String> {
             "[FFmpeg] Using existing FFmpeg binary at {}",
             ffmpeg_path.display()
         );
-        FFMPEG_PATH.set(ffmpeg_path).unwrap();
+        let _ = FFMPEG_PATH.set(ffmpeg_path);
         return Ok(());
     }
 
@@ -338,11 +349,12 @@ pub fn init_ffmpeg() -> Result<(), String> {
         "[FFmpeg] FFmpeg successfully initialized in {:?}",
         ffmpeg_path
     );
-    FFMPEG_PATH.set(ffmpeg_path).unwrap();
+    let _ = FFMPEG_PATH.set(ffmpeg_path);
     Ok(())
 }
 
 pub fn init_ffprobe() -> Result<(), String> {
+    let _init = INIT_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
     if FFPROBE_PATH.get().is_some() {
         log::info!("[FFmpeg] FFprobe already initialized");
         return Ok(());
@@ -359,7 +371,7 @@ pub fn init_ffprobe() -> Result<(), String> {
             "[FFmpeg] Using existing FFprobe binary at {}",
             ffprobe_path.display()
         );
-        FFPROBE_PATH.set(ffprobe_path).unwrap();
+        let _ = FFPROBE_PATH.set(ffprobe_path);
         return Ok(());
     }
 
@@ -434,21 +446,91 @@ pub fn init_ffprobe() -> Result<(), String> {
         "[FFmpeg] FFprobe successfully initialized in {:?}",
         ffprobe_path
     );
-    FFPROBE_PATH.set(ffprobe_path).unwrap();
+    let _ = FFPROBE_PATH.set(ffprobe_path);
     Ok(())
 }
 
+/// Latest `-progress` report of a running ffmpeg
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct FFmpegProgress {
+    pub frame: u64,
+    pub fps: f64,
+    pub bitrate_kbps: f64,
+    // Bytes written to the current segment
+    pub total_size: u64,
+    pub dropped_frames: u64,
+    pub duplicated_frames: u64,
+    pub speed: f64,
+    // Media time encoded so far in the current segment
+    pub out_time_ms: u64,
+}
+
+impl FFmpegProgress {
+    // Applies one `key=value` line; returns true when the "progress" key closes a report
+    fn apply(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "frame" => self.frame = value.parse().unwrap_or(self.frame),
+            "fps" => self.fps = value.parse().unwrap_or(0.0),
+            "bitrate" => {
+                self.bitrate_kbps = value
+                    .trim_end_matches("kbits/s")
+                    .trim()
+                    .parse()
+                    .unwrap_or(0.0)
+            }
+            "total_size" => self.total_size = value.parse().unwrap_or(self.total_size),
+            "drop_frames" => self.dropped_frames = value.parse().unwrap_or(self.dropped_frames),
+            "dup_frames" => {
+                self.duplicated_frames = value.parse().unwrap_or(self.duplicated_frames)
+            }
+            "speed" => self.speed = value.trim_end_matches('x').trim().parse().unwrap_or(0.0),
+            "out_time_us" => {
+                self.out_time_ms = value.parse::<u64>().map_or(self.out_time_ms, |us| us / 1000)
+            }
+            "progress" => return true,
+            _ => {}
+        }
+        false
+    }
+}
+
+#[derive(Default)]
+struct ProgressState {
+    latest: FFmpegProgress,
+    // Last time the frame counter moved, used to spot a stalled encoder
+    frame_advanced_at: Option<Instant>,
+    // When the segment's first frame was captured, estimated from the first report
+    first_frame_at: Option<Instant>,
+}
+
+/// Input tweaks for recording a window or a region instead of a whole monitor
+#[derive(Default, Clone)]
+pub struct CaptureOptions {
+    // Extra demuxer options placed before `-i`, e.g. x11grab's `-window_id`
+    pub input_args: Vec<String>,
+    // Filter applied to the captured frames before scaling, e.g. a crop
+    pub filter: Option<String>,
+    // Let the device size the frames itself (gdigrab sizes window captures)
+    pub auto_size: bool,
+}
+
 // #[cfg(not(target_os = "macos"))]
 pub struct FFmpegRecorder {
     x: i32,
     y: i32,
     width: u32,
     height: u32,
-    fps: u32,
+    encoding: EncodingProfile,
     output_path: PathBuf,
     process: Option<std::process::Child>,
     input_format: Option<String>,
     input_device: Option<String>,
+    capture: CaptureOptions,
+    // Microphone/loopback inputs, mixed into a single audio track
+    audio: Vec<AudioInput>,
+    progress: Arc<Mutex<ProgressState>>,
+    // Every start/resume writes its own segment; stop() merges them into output_path
+    segments: Vec<PathBuf>,
 }
 
 // #[cfg(not(target_os = "macos"))]
@@ -458,17 +540,18 @@ impl FFmpegRecorder {
         y: i32,
         width: u32,
         height: u32,
-        fps: u32,
+        encoding: EncodingProfile,
         output_path: PathBuf,
         input_format: String,
         input_device: String,
     ) -> Self {
         log::info!(
-            "[FFmpeg] Creating new recorder with input format {}: {}x{} @ {} fps -> {}",
+            "[FFmpeg] Creating new recorder with input format {}: {}x{} @ {} fps ({}) -> {}",
             input_format,
             width,
             height,
-            fps,
+            encoding.fps,
+            encoding.codec,
             output_path.display()
         );
 
@@ -477,40 +560,94 @@ impl FFmpegRecorder {
             y,
             width,
             height,
-            fps,
+            encoding,
             output_path,
             process: None,
             input_format: Some(input_format),
             input_device: Some(input_device),
+            capture: CaptureOptions::default(),
+            audio: Vec::new(),
+            progress: Arc::new(Mutex::new(ProgressState::default())),
+            segments: Vec::new(),
         }
     }
 
+    /// Creates a recorder that reads raw RGB frames from stdin instead of a capture device
+    pub fn new_with_raw_input(
+        width: u32,
+        height: u32,
+        encoding: EncodingProfile,
+        output_path: PathBuf,
+    ) -> Self {
+        log::info!(
+            "[FFmpeg] Creating new recorder with raw stdin input: {}x{} @ {} fps ({}) -> {}",
+            width,
+            height,
+            encoding.fps,
+            encoding.codec,
+            output_path.display()
+        );
+
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            encoding,
+            output_path,
+            process: None,
+            input_format: None,
+            input_device: None,
+            capture: CaptureOptions::default(),
+            audio: Vec::new(),
+            progress: Arc::new(Mutex::new(ProgressState::default())),
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn with_capture_options(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_audio(mut self, audio: Vec<AudioInput>) -> Self {
+        self.audio = audio;
+        self
+    }
+
     pub fn start(&mut self) -> Result<(), String> {
+        self.start_with_stdin(Stdio::piped())
+    }
+
+    /// Starts ffmpeg with the given stdin. Capture devices keep a pipe so `stop` can send 'q';
+    /// raw input recorders pass the producer's stdout here and stop once it closes.
+    pub fn start_with_stdin(&mut self, stdin: Stdio) -> Result<(), String> {
         log::info!(
             "[FFmpeg] Starting recording: starting ({},{}) for {}x{} @ {} fps",
             self.x,
             self.y,
             self.width,
             self.height,
-            self.fps
+            self.encoding.fps
         );
         let ffmpeg = FFMPEG_PATH.get().ok_or_else(|| {
             log::info!("[FFmpeg] Error: FFmpeg not initialized");
             "FFmpeg not initialized".to_string()
         })?;
 
+        let segment_path = self.segment_path(self.segments.len());
         let mut args: Vec<String> = Vec::new();
 
         // Input format args
         if let (Some(format), Some(device)) = (&self.input_format, &self.input_device) {
-            args.extend([
-                "-f".to_string(),
-                format.clone(),
-                "-video_size".to_string(),
-                format!("{}x{}", self.width, self.height),
-                "-framerate".to_string(),
-                self.fps.to_string(),
-            ]);
+            args.extend(["-f".to_string(), format.clone()]);
+            if !self.capture.auto_size {
+                args.extend([
+                    "-video_size".to_string(),
+                    format!("{}x{}", self.width, self.height),
+                ]);
+            }
+            args.extend(["-framerate".to_string(), self.encoding.fps.to_string()]);
 
             // Platform specific options
             if format == "gdigrab" {
@@ -530,6 +667,7 @@ impl FFmpegRecorder {
                 args.extend(["-capture_cursor".to_string(), "1".to_string()]);
             }
 
+            args.extend(self.capture.input_args.iter().cloned());
             args.extend(["-i".to_string(), device.clone()]);
         } else {
             // Fallback to raw video input
@@ -541,30 +679,37 @@ impl FFmpegRecorder {
                 "-video_size".to_string(),
                 format!("{}x{}", self.width, self.height),
                 "-framerate".to_string(),
-                self.fps.to_string(),
+                self.encoding.fps.to_string(),
                 "-i".to_string(),
                 "-".to_string(), // Read from stdin
             ]);
         }
 
+        // Audio devices follow the video as inputs 1..n
+        for input in &self.audio {
+            args.extend([
+                "-thread_queue_size".to_string(),
+                "1024".to_string(),
+                "-f".to_string(),
+                input.format.clone(),
+                "-i".to_string(),
+                input.device.clone(),
+            ]);
+        }
+
         // Output encoding args
+        args.extend(self.encoding_args());
+        args.extend(self.audio_args());
         args.extend([
-            "-c:v".to_string(),
-            "libx264".to_string(),
-            "-preset".to_string(),
-            "ultrafast".to_string(),
-            "-crf".to_string(),
-            "23".to_string(), // Balance between quality and file size
-            "-pix_fmt".to_string(),
-            "yuv420p".to_string(), // Required for compatibility
+            // Fragmented MP4 stays playable if ffmpeg or the app dies mid-recording;
+            // merge_segments remuxes it with +faststart once the recording ends
             "-movflags".to_string(),
-            "+faststart".to_string(), // Enable streaming playback
-            "-profile:v".to_string(),
-            "high".to_string(),
-            "-tune".to_string(),
-            "zerolatency".to_string(), // Reduce encoding latency
-            "-y".to_string(),          // Overwrite output file
-            self.output_path.to_str().unwrap().to_string(),
+            "+frag_keyframe+empty_moov+default_base_moof".to_string(),
+            // Machine readable stats on stdout for the health watchdog
+            "-progress".to_string(),
+            "pipe:1".to_string(),
+            "-y".to_string(), // Overwrite output file
+            segment_path.to_str().unwrap().to_string(),
         ]);
 
         log::info!("[FFmpeg] Command: {} {}", ffmpeg.display(), args.join(" "));
@@ -576,7 +721,7 @@ impl FFmpegRecorder {
         }
         let mut process = command
             .args(&args)
-            .stdin(Stdio::piped())
+            .stdin(stdin)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
@@ -586,12 +731,40 @@ impl FFmpegRecorder {
             })?;
 
         // Spawn threads to handle stdout and stderr in real-time
+        if let Ok(mut progress) = self.progress.lock() {
+            *progress = ProgressState {
+                latest: FFmpegProgress::default(),
+                frame_advanced_at: Some(Instant::now()),
+                first_frame_at: None,
+            };
+        }
+
         if let Some(stdout) = process.stdout.take() {
             let stdout_reader = std::io::BufReader::new(stdout);
+            let progress = self.progress.clone();
             thread::spawn(move || {
                 use std::io::BufRead;
+                let mut report = FFmpegProgress::default();
                 for line in stdout_reader.lines() {
                     if let Ok(line) = line {
+                        // Progress reports come every half second, keep them out of the log
+                        if let Some((key, value)) = line.split_once('=') {
+                            if report.apply(key.trim(), value.trim()) {
+                                if let Ok(mut progress) = progress.lock() {
+                                    if report.frame != progress.latest.frame {
+                                        progress.frame_advanced_at = Some(Instant::now());
+                                    }
+                                    // The report arrives after encoding, so step back by the
+                                    // media time it covers to find the first frame
+                                    if progress.first_frame_at.is_none() && report.frame > 0 {
+                                        progress.first_frame_at = Instant::now()
+                                            .checked_sub(Duration::from_millis(report.out_time_ms));
+                                    }
+                                    progress.latest = report.clone();
+                                }
+                            }
+                            continue;
+                        }
                         log::info!("[FFmpeg] stdout: {}", line);
                         let _ = crate::core::record::log_ffmpeg(&line, false);
                     }
@@ -642,8 +815,8 @@ impl FFmpegRecorder {
                 }
 
                 // Cleanup any partial output file
-                if self.output_path.exists() {
-                    if let Err(e) = fs::remove_file(&self.output_path) {
+                if segment_path.exists() {
+                    if let Err(e) = fs::remove_file(&segment_path) {
                         log::info!(
                             "[FFmpeg] Warning: Failed to cleanup partial output file: {}",
                             e
@@ -681,6 +854,7 @@ impl FFmpegRecorder {
                 }
 
                 self.process = Some(process);
+                self.segments.push(segment_path);
                 Ok(())
             }
             Err(e) => {
@@ -690,8 +864,193 @@ impl FFmpegRecorder {
         }
     }
 
+    /// Builds the codec arguments for the configured encoding profile
+    fn encoding_args(&self) -> Vec<String> {
+        let encoding = &self.encoding;
+        let mut args = Vec::new();
+        let mut filters: Vec<String> = self.capture.filter.iter().cloned().collect();
+
+        // Downscale captures larger than the configured maximum, keeping the aspect ratio
+        let max_width = encoding.max_width.unwrap_or(self.width);
+        let max_height = encoding.max_height.unwrap_or(self.height);
+        if self.width > max_width || self.height > max_height {
+            filters.push(format!(
+                "scale=w={}:h={}:force_original_aspect_ratio=decrease:force_divisible_by=2",
+                max_width.min(self.width),
+                max_height.min(self.height)
+            ));
+        }
+        if !filters.is_empty() {
+            args.extend(["-vf".to_string(), filters.join(",")]);
+        }
+
+        args.extend(["-c:v".to_string(), encoding.codec.clone()]);
+        match encoding.codec.as_str() {
+            "libvpx-vp9" => {
+                // VP9 has no presets; map them onto deadline/cpu-used instead
+                let (deadline, cpu_used) = match encoding.preset.as_str() {
+                    "ultrafast" | "superfast" | "veryfast" => ("realtime", "8"),
+                    "faster" | "fast" | "medium" => ("good", "4"),
+                    _ => ("good", "2"),
+                };
+                args.extend([
+                    "-deadline".to_string(),
+                    deadline.to_string(),
+                    "-cpu-used".to_string(),
+                    cpu_used.to_string(),
+                    "-row-mt".to_string(),
+                    "1".to_string(),
+                    "-crf".to_string(),
+                    encoding.crf.to_string(),
+                    "-b:v".to_string(),
+                    "0".to_string(), // Constant quality mode
+                ]);
+            }
+            "libx265" => {
+                args.extend([
+                    "-preset".to_string(),
+                    encoding.preset.clone(),
+                    "-crf".to_string(),
+                    encoding.crf.to_string(),
+                    "-tag:v".to_string(),
+                    "hvc1".to_string(), // Lets QuickTime/Safari play HEVC in MP4
+                ]);
+            }
+            _ => {
+                args.extend([
+                    "-preset".to_string(),
+                    encoding.preset.clone(),
+                    "-crf".to_string(),
+                    encoding.crf.to_string(), // Balance between quality and file size
+                    "-profile:v".to_string(),
+                    "high".to_string(),
+                    "-tune".to_string(),
+                    "zerolatency".to_string(), // Reduce encoding latency
+                ]);
+            }
+        }
+
+        args.extend([
+            "-pix_fmt".to_string(),
+            "yuv420p".to_string(), // Required for compatibility
+        ]);
+        args
+    }
+
+    /// When the current segment's first frame was captured, once ffmpeg has reported it
+    pub fn first_frame_at(&self) -> Option<Instant> {
+        self.progress.lock().ok()?.first_frame_at
+    }
+
+    /// Checks that the current segment is still being encoded. Returns the latest progress
+    /// report, or why the recording failed if ffmpeg exited or stopped producing frames.
+    pub fn health(&mut self) -> Result<FFmpegProgress, String> {
+        let progress = self.progress.lock().map_err(|e| e.to_string())?;
+
+        // Paused or stopped, nothing is expected to run
+        let Some(process) = self.process.as_mut() else {
+            return Ok(progress.latest.clone());
+        };
+
+        match process.try_wait() {
+            Ok(Some(status)) => return Err(format!("FFmpeg exited unexpectedly ({})", status)),
+            Ok(None) => {}
+            Err(e) => return Err(format!("Failed to check FFmpeg process status: {}", e)),
+        }
+
+        if progress
+            .frame_advanced_at
+            .is_some_and(|at| at.elapsed() > STALL_TIMEOUT)
+        {
+            return Err(format!(
+                "FFmpeg has not encoded a frame for {} seconds",
+                STALL_TIMEOUT.as_secs()
+            ));
+        }
+
+        Ok(progress.latest.clone())
+    }
+
+    fn audio_args(&self) -> Vec<String> {
+        if self.audio.is_empty() {
+            return Vec::new();
+        }
+
+        let mut args = vec!["-map".to_string(), "0:v".to_string()];
+        if self.audio.len() == 1 {
+            args.extend(["-map".to_string(), "1:a".to_string()]);
+        } else {
+            let inputs: String = (1..=self.audio.len())
+                .map(|i| format!("[{}:a]", i))
+                .collect();
+            args.extend([
+                "-filter_complex".to_string(),
+                format!(
+                    "{}amix=inputs={}:duration=longest[aout]",
+                    inputs,
+                    self.audio.len()
+                ),
+                "-map".to_string(),
+                "[aout]".to_string(),
+            ]);
+        }
+        args.extend([
+            "-c:a".to_string(),
+            "aac".to_string(),
+            "-b:a".to_string(),
+            "128k".to_string(),
+        ]);
+
+        // Live audio never ends on its own, so stop with the piped video
+        if self.input_format.is_none() {
+            args.push("-shortest".to_string());
+        }
+        args
+    }
+
     pub fn stop(&mut self) -> Result<(), String> {
         log::info!("[FFmpeg] Stopping recording");
+        // Merge whatever segments exist even if the last one failed to finalize
+        let finished = self.finish_segment();
+        let merged = self.merge_segments();
+        finished.and(merged)
+    }
+
+    /// Finalizes the current segment; call `start` again to resume into a new one
+    pub fn pause(&mut self) -> Result<(), String> {
+        log::info!("[FFmpeg] Pausing recording");
+        self.finish_segment()
+    }
+
+    fn segment_path(&self, index: usize) -> PathBuf {
+        let stem = self
+            .output_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "recording".to_string());
+        let extension = self
+            .output_path
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_else(|| "mp4".to_string());
+        self.output_path
+            .with_file_name(format!("{}.part{}.{}", stem, index, extension))
+    }
+
+    fn merge_segments(&mut self) -> Result<(), String> {
+        let segments: Vec<PathBuf> = self
+            .segments
+            .drain(..)
+            .filter(|p| p.metadata().map(|m| m.len() > 0).unwrap_or(false))
+            .collect();
+        merge_segments(&segments, &self.output_path)
+    }
+
+    fn finish_segment(&mut self) -> Result<(), String> {
+        let Some(segment_path) = self.segments.last().cloned() else {
+            log::info!("[FFmpeg] No active process to stop");
+            return Ok(());
+        };
         if let Some(mut process) = self.process.take() {
             // Send 'q' to FFmpeg to stop recording gracefully
             if let Some(mut stdin) = process.stdin.take() {
@@ -746,15 +1105,15 @@ impl FFmpegRecorder {
             }
 
             // Check if output file exists and has size
-            if !self.output_path.exists() {
+            if !segment_path.exists() {
                 log::info!(
                     "[FFmpeg] Error: Failed to create output file at {}",
-                    self.output_path.display()
+                    segment_path.display()
                 );
                 return Err("FFmpeg failed to create output file".to_string());
             }
 
-            let file_size = fs::metadata(&self.output_path)
+            let file_size = fs::metadata(&segment_path)
                 .map_err(|e| {
                     log::info!("[FFmpeg] Error: Failed to get output file metadata: {}", e);
                     format!("Failed to get output file metadata: {}", e)
@@ -764,14 +1123,14 @@ impl FFmpegRecorder {
             if file_size == 0 {
                 log::info!(
                     "[FFmpeg] Error: Created empty output file at {}",
-                    self.output_path.display()
+                    segment_path.display()
                 );
                 return Err("FFmpeg created empty output file".to_string());
             }
 
             log::info!(
                 "[FFmpeg] Recording saved successfully: {} ({} bytes)",
-                self.output_path.display(),
+                segment_path.display(),
                 file_size
             );
         } else {
@@ -780,3 +1139,252 @@ impl FFmpegRecorder {
         Ok(())
     }
 }
+
+/// Merges fragmented recording segments into a single faststart file and removes them.
+/// A lone segment that fails to remux is kept as-is, since fragmented MP4 still plays.
+pub fn merge_segments(segments: &[PathBuf], output_path: &Path) -> Result<(), String> {
+    if segments.is_empty() {
+        return Ok(());
+    }
+
+    if let Err(e) = concat_segments(segments, output_path) {
+        if segments.len() > 1 {
+            return Err(e);
+        }
+        log::info!("[FFmpeg] Warning: Remux failed, keeping fragmented segment: {}", e);
+        return fs::rename(&segments[0], output_path)
+            .map_err(|e| format!("Failed to move recording segment: {}", e));
+    }
+
+    for segment in segments {
+        if let Err(e) = fs::remove_file(segment) {
+            log::info!("[FFmpeg] Warning: Failed to remove segment: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Joins recording segments into one file without re-encoding
+pub fn concat_segments(segments: &[PathBuf], output_path: &Path) -> Result<(), String> {
+    let ffmpeg = FFMPEG_PATH
+        .get()
+        .ok_or_else(|| "FFmpeg not initialized".to_string())?;
+
+    log::info!(
+        "[FFmpeg] Concatenating {} segments into {}",
+        segments.len(),
+        output_path.display()
+    );
+
+    let list_path = output_path.with_extension("segments.txt");
+    let list = segments
+        .iter()
+        .map(|segment| {
+            format!(
+                "file '{}'",
+                segment.to_string_lossy().replace('\'', "'\\''")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    fs::write(&list_path, list).map_err(|e| format!("Failed to write segment list: {}", e))?;
+
+    let mut command = Command::new(ffmpeg);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW constant
+    }
+    let output = command
+        .args(["-f", "concat", "-safe", "0", "-i"])
+        .arg(&list_path)
+        .args(["-c", "copy", "-movflags", "+faststart", "-y"])
+        .arg(output_path)
+        .output()
+        .map_err(|e| format!("Failed to execute FFmpeg concat: {}", e));
+    let _ = fs::remove_file(&list_path);
+    let output = output?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        log::info!("[FFmpeg] Error: Concat failed: {}", error);
+        return Err(format!("Failed to merge recording segments: {}", error));
+    }
+
+    Ok(())
+}
+
+/// Screen capture inputs the recorder knows how to drive. `pipewire` is not an ffmpeg
+/// device: Wayland frames come from GStreamer and reach ffmpeg through the rawvideo demuxer.
+pub const CAPTURE_INPUTS: &[&str] = &["x11grab", "gdigrab", "avfoundation", "pipewire"];
+
+static FFMPEG_CAPABILITIES: OnceLock<FFmpegCapabilities> = OnceLock::new();
+
+/// What the resolved ffmpeg binary can actually do on this machine
+#[derive(Serialize, Clone, Debug)]
+pub struct FFmpegCapabilities {
+    pub path: String,
+    pub video_encoders: Vec<String>,
+    pub demuxers: Vec<String>,
+    pub input_devices: Vec<String>,
+    pub capture_inputs: Vec<String>,
+    pub codecs: Vec<String>,
+}
+
+impl FFmpegCapabilities {
+    pub fn has_encoder(&self, name: &str) -> bool {
+        self.video_encoders.iter().any(|e| e == name)
+    }
+
+    pub fn has_input_device(&self, name: &str) -> bool {
+        self.input_devices.iter().any(|d| d == name)
+    }
+
+    pub fn has_capture_input(&self, name: &str) -> bool {
+        self.capture_inputs.iter().any(|i| i == name)
+    }
+
+    /// Returns the profile unchanged when its encoder is available, otherwise the same
+    /// profile moved onto the first supported codec this build does include
+    pub fn select_encoding(&self, encoding: &EncodingProfile) -> Result<EncodingProfile, String> {
+        if self.has_encoder(&encoding.codec) {
+            return Ok(encoding.clone());
+        }
+
+        let fallback = self.codecs.first().ok_or_else(|| {
+            format!(
+                "The installed FFmpeg build has none of the supported encoders ({})",
+                SUPPORTED_CODECS.join(", ")
+            )
+        })?;
+        log::warn!(
+            "[FFmpeg] Encoder {} is not available, falling back to {}",
+            encoding.codec,
+            fallback
+        );
+
+        let mut profile = EncodingProfile {
+            codec: fallback.clone(),
+            ..encoding.clone()
+        };
+        // CRF ranges differ between codecs (VP9 goes up to 63)
+        if profile.validate().is_err() {
+            profile.crf = EncodingProfile::default().crf;
+        }
+        Ok(profile)
+    }
+}
+
+/// Probes `-encoders`, `-formats` and `-devices` of the resolved ffmpeg binary.
+/// The result is cached since the binary cannot change once initialized.
+pub fn probe_capabilities() -> Result<FFmpegCapabilities, String> {
+    if let Some(capabilities) = FFMPEG_CAPABILITIES.get() {
+        return Ok(capabilities.clone());
+    }
+
+    let ffmpeg = FFMPEG_PATH
+        .get()
+        .ok_or_else(|| "FFmpeg not initialized".to_string())?;
+
+    let video_encoders: Vec<String> = list_ffmpeg_entries("-encoders")?
+        .into_iter()
+        .filter(|(flags, _)| flags.starts_with('V'))
+        .map(|(_, name)| name)
+        .collect();
+    let demuxers: Vec<String> = list_ffmpeg_entries("-formats")?
+        .into_iter()
+        .filter(|(flags, _)| flags.starts_with('D'))
+        .map(|(_, name)| name)
+        .collect();
+    let input_devices: Vec<String> = list_ffmpeg_entries("-devices")?
+        .into_iter()
+        .filter(|(flags, _)| flags.starts_with('D'))
+        .map(|(_, name)| name)
+        .collect();
+
+    let capture_inputs = CAPTURE_INPUTS
+        .iter()
+        .filter(|input| match **input {
+            "pipewire" => pipewire_available(&demuxers),
+            device => input_devices.iter().any(|d| d == device),
+        })
+        .map(|input| input.to_string())
+        .collect();
+    let codecs = SUPPORTED_CODECS
+        .iter()
+        .filter(|codec| video_encoders.iter().any(|e| e == *codec))
+        .map(|codec| codec.to_string())
+        .collect();
+
+    let capabilities = FFmpegCapabilities {
+        path: ffmpeg.display().to_string(),
+        video_encoders,
+        demuxers,
+        input_devices,
+        capture_inputs,
+        codecs,
+    };
+    log::info!(
+        "[FFmpeg] Capture inputs: [{}], codecs: [{}]",
+        capabilities.capture_inputs.join(", "),
+        capabilities.codecs.join(", ")
+    );
+
+    let _ = FFMPEG_CAPABILITIES.set(capabilities.clone());
+    Ok(capabilities)
+}
+
+#[cfg(target_os = "linux")]
+fn pipewire_available(demuxers: &[String]) -> bool {
+    demuxers.iter().any(|d| d == "rawvideo") && crate::tools::wayland::pipewire_producer_available()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pipewire_available(_demuxers: &[String]) -> bool {
+    false
+}
+
+/// Runs a listing option such as `-encoders` and returns its `(flags, name)` rows.
+/// The rows follow a dashed separator as wide as the flag column, e.g. " ------" then
+/// " V....D libx264   description", or " ---" then " D d x11grab   X11 screen capture".
+fn list_ffmpeg_entries(option: &str) -> Result<Vec<(String, String)>, String> {
+    let ffmpeg = FFMPEG_PATH
+        .get()
+        .ok_or_else(|| "FFmpeg not initialized".to_string())?;
+
+    let mut command = Command::new(ffmpeg);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW constant
+    }
+    let output = command
+        .args(["-hide_banner", option])
+        .output()
+        .map_err(|e| format!("Failed to run FFmpeg {}: {}", option, e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let flag_width = lines
+        .by_ref()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && line.chars().all(|c| c == '-'))
+        .map(|separator| separator.len())
+        .ok_or_else(|| format!("Unexpected output from FFmpeg {}", option))?;
+
+    let mut entries = Vec::new();
+    for line in lines {
+        let (Some(flags), Some(rest)) = (line.get(1..1 + flag_width), line.get(1 + flag_width..))
+        else {
+            continue;
+        };
+        let Some(names) = rest.split_whitespace().next() else {
+            continue;
+        };
+        // Formats list their aliases together, e.g. "mov,mp4,m4a,3gp,3g2,mj2"
+        for name in names.split(',') {
+            entries.push((flags.to_string(), name.to_string()));
+        }
+    }
+    Ok(entries)
+}
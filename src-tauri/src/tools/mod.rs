@@ -1,4 +1,7 @@
 // Re-export all tool modules
+pub mod audio;
 pub mod axtree;
 pub mod ffmpeg;
 pub mod pipeline;
+#[cfg(target_os = "linux")]
+pub mod wayland;
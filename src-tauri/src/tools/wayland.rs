@@ -0,0 +1,378 @@
+use crate::tools::audio::AudioInput;
+use crate::tools::ffmpeg::{FFmpegProgress, FFmpegRecorder};
+use crate::utils::settings::EncodingProfile;
+use ashpd::desktop::{
+    PersistMode, Session,
+    screencast::{CursorMode, Screencast, SourceType},
+};
+use std::io::{BufRead, BufReader};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::Sender;
+
+const GST_LAUNCH: &str = "gst-launch-1.0";
+const GST_INSPECT: &str = "gst-inspect-1.0";
+
+// The portal shows a source picker the first time, so give the user time to answer it
+const PORTAL_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Restore token handed out by the portal so later recordings skip the source picker
+static RESTORE_TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
+/// Returns true when screen capture has to go through the xdg-desktop-portal.
+/// `OMNIMINDS_CAPTURE_BACKEND=x11|wayland` overrides the detection, which is useful
+/// when testing against a headless weston/mutter session.
+pub fn is_wayland_session() -> bool {
+    if let Ok(backend) = std::env::var("OMNIMINDS_CAPTURE_BACKEND") {
+        return backend.eq_ignore_ascii_case("wayland");
+    }
+
+    match std::env::var("XDG_SESSION_TYPE") {
+        Ok(session_type) => session_type.eq_ignore_ascii_case("wayland"),
+        Err(_) => std::env::var("WAYLAND_DISPLAY").is_ok() && std::env::var("DISPLAY").is_err(),
+    }
+}
+
+/// Returns true when gst-launch can read PipeWire streams (the pipewiresrc element exists)
+pub fn pipewire_producer_available() -> bool {
+    Command::new(GST_INSPECT)
+        .args(["--exists", "pipewiresrc"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// A PipeWire stream granted by the ScreenCast portal
+struct PortalStream {
+    node_id: u32,
+    fd: OwnedFd,
+    width: u32,
+    height: u32,
+}
+
+/// Keeps the portal session open until the recorder is stopped
+struct PortalSession {
+    stream: PortalStream,
+    stop: Sender<()>,
+}
+
+impl PortalSession {
+    // Blocks until the user answers the source picker, so it must not run on a runtime
+    // worker; `start_recording` starts the recorders on a blocking thread
+    fn open(capture_window: bool) -> Result<Self, String> {
+        log::info!("[Wayland] Requesting screen cast session from xdg-desktop-portal");
+        let (result_tx, result_rx) = mpsc::channel();
+        let (stop_tx, mut stop_rx) = tauri::async_runtime::channel::<()>(1);
+
+        tauri::async_runtime::spawn(async move {
+            let proxy = match Screencast::new().await {
+                Ok(proxy) => proxy,
+                Err(e) => {
+                    let _ = result_tx.send(Err(format!("ScreenCast portal unavailable: {}", e)));
+                    return;
+                }
+            };
+
+            let session = match negotiate_stream(&proxy, capture_window).await {
+                Ok((session, stream)) => {
+                    let _ = result_tx.send(Ok(stream));
+                    session
+                }
+                Err(e) => {
+                    let _ = result_tx.send(Err(format!("ScreenCast portal request failed: {}", e)));
+                    return;
+                }
+            };
+
+            // Hold the session until the recorder asks us to close it, or close it right
+            // away when `open` stopped waiting before the stream was granted
+            let _ = stop_rx.recv().await;
+            if let Err(e) = session.close().await {
+                log::warn!("[Wayland] Failed to close portal session: {}", e);
+            }
+            log::info!("[Wayland] Portal session closed");
+        });
+
+        let stream = match result_rx.recv_timeout(PORTAL_TIMEOUT) {
+            Ok(stream) => stream?,
+            Err(_) => {
+                let _ = stop_tx.try_send(());
+                return Err("Timed out waiting for the screen cast portal".to_string());
+            }
+        };
+
+        log::info!(
+            "[Wayland] Portal granted PipeWire node {} ({}x{})",
+            stream.node_id,
+            stream.width,
+            stream.height
+        );
+
+        Ok(Self {
+            stream,
+            stop: stop_tx,
+        })
+    }
+
+    fn close(self) {
+        let _ = self.stop.try_send(());
+    }
+}
+
+async fn negotiate_stream(
+    proxy: &Screencast<'static>,
+    capture_window: bool,
+) -> ashpd::Result<(Session<'static, Screencast<'static>>, PortalStream)> {
+    let session = proxy.create_session().await?;
+
+    // The saved token restores the last monitor; windows are picked again every time
+    let (source_type, restore_token, persist_mode) = if capture_window {
+        (SourceType::Window, None, PersistMode::DoNot)
+    } else {
+        (
+            SourceType::Monitor,
+            RESTORE_TOKEN.lock().ok().and_then(|token| token.clone()),
+            PersistMode::Application,
+        )
+    };
+
+    proxy
+        .select_sources(
+            &session,
+            CursorMode::Embedded,
+            source_type.into(),
+            false,
+            restore_token.as_deref(),
+            persist_mode,
+        )
+        .await?;
+
+    let response = proxy.start(&session, None).await?.response()?;
+    if let Some(token) = response.restore_token().filter(|_| !capture_window) {
+        if let Ok(mut saved) = RESTORE_TOKEN.lock() {
+            *saved = Some(token.to_string());
+        }
+    }
+
+    let stream = response.streams().first().ok_or(ashpd::Error::NoResponse)?;
+    let (width, height) = stream.size().unwrap_or((0, 0));
+    let node_id = stream.pipe_wire_node_id();
+    let fd = proxy.open_pipe_wire_remote(&session).await?;
+
+    Ok((
+        session,
+        PortalStream {
+            node_id,
+            fd,
+            width: width.max(0) as u32,
+            height: height.max(0) as u32,
+        },
+    ))
+}
+
+/// Records a Wayland session by decoding the portal's PipeWire stream with
+/// gst-launch and piping raw RGB frames into ffmpeg's stdin.
+pub struct WaylandRecorder {
+    encoding: EncodingProfile,
+    output_path: PathBuf,
+    // Ask the portal for a single window instead of a monitor
+    capture_window: bool,
+    audio: Vec<AudioInput>,
+    portal: Option<PortalSession>,
+    producer: Option<Child>,
+    ffmpeg: Option<FFmpegRecorder>,
+}
+
+impl WaylandRecorder {
+    pub fn new(encoding: EncodingProfile, output_path: PathBuf, capture_window: bool) -> Self {
+        log::info!(
+            "[Wayland] Creating new portal recorder @ {} fps -> {}",
+            encoding.fps,
+            output_path.display()
+        );
+
+        Self {
+            encoding,
+            output_path,
+            capture_window,
+            audio: Vec::new(),
+            portal: None,
+            producer: None,
+            ffmpeg: None,
+        }
+    }
+
+    pub fn with_audio(mut self, audio: Vec<AudioInput>) -> Self {
+        self.audio = audio;
+        self
+    }
+
+    /// Starts capturing, or resumes into a new segment after `pause`
+    pub fn start(&mut self) -> Result<(), String> {
+        if self.portal.is_none() {
+            self.portal = Some(PortalSession::open(self.capture_window)?);
+        }
+        let stream = &self.portal.as_ref().unwrap().stream;
+
+        if stream.width == 0 || stream.height == 0 {
+            return Err("Screen cast portal did not report a stream size".to_string());
+        }
+
+        let mut producer = spawn_pipewire_producer(stream, self.encoding.fps)?;
+        let stdout = producer
+            .stdout
+            .take()
+            .ok_or_else(|| "Failed to capture gst-launch output".to_string())?;
+
+        let (width, height) = (stream.width, stream.height);
+        let encoding = self.encoding.clone();
+        let output_path = self.output_path.clone();
+        let audio = self.audio.clone();
+        let ffmpeg = self.ffmpeg.get_or_insert_with(|| {
+            FFmpegRecorder::new_with_raw_input(width, height, encoding, output_path)
+                .with_audio(audio)
+        });
+        if let Err(e) = ffmpeg.start_with_stdin(Stdio::from(stdout)) {
+            let _ = producer.kill();
+            let _ = producer.wait();
+            return Err(e);
+        }
+
+        self.producer = Some(producer);
+        Ok(())
+    }
+
+    pub fn health(&mut self) -> Result<FFmpegProgress, String> {
+        if let Some(producer) = self.producer.as_mut() {
+            if let Ok(Some(status)) = producer.try_wait() {
+                return Err(format!("gst-launch exited unexpectedly ({})", status));
+            }
+        }
+
+        match self.ffmpeg.as_mut() {
+            Some(ffmpeg) => ffmpeg.health(),
+            None => Ok(FFmpegProgress::default()),
+        }
+    }
+
+    pub fn first_frame_at(&self) -> Option<Instant> {
+        self.ffmpeg.as_ref().and_then(|ffmpeg| ffmpeg.first_frame_at())
+    }
+
+    /// Stops the frame producer but keeps the portal session so resuming needs no prompt
+    pub fn pause(&mut self) -> Result<(), String> {
+        log::info!("[Wayland] Pausing portal recorder");
+        self.stop_producer();
+        match self.ffmpeg.as_mut() {
+            Some(ffmpeg) => ffmpeg.pause(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn stop(&mut self) -> Result<(), String> {
+        log::info!("[Wayland] Stopping portal recorder");
+        self.stop_producer();
+
+        let result = match self.ffmpeg.take() {
+            Some(mut ffmpeg) => ffmpeg.stop(),
+            None => Ok(()),
+        };
+
+        if let Some(portal) = self.portal.take() {
+            portal.close();
+        }
+
+        result
+    }
+
+    // Closing the producer ends ffmpeg's stdin, which lets it finalize the segment
+    fn stop_producer(&mut self) {
+        if let Some(mut producer) = self.producer.take() {
+            if let Err(e) = producer.kill() {
+                log::info!("[Wayland] Warning: Failed to stop gst-launch: {}", e);
+            }
+            let _ = producer.wait();
+        }
+    }
+}
+
+impl Drop for WaylandRecorder {
+    fn drop(&mut self) {
+        self.stop_producer();
+        if let Some(portal) = self.portal.take() {
+            portal.close();
+        }
+    }
+}
+
+fn spawn_pipewire_producer(stream: &PortalStream, fps: u32) -> Result<Child, String> {
+    let fd = stream.fd.as_raw_fd();
+    let args = [
+        "-q".to_string(),
+        "pipewiresrc".to_string(),
+        format!("fd={}", fd),
+        format!("path={}", stream.node_id),
+        "do-timestamp=true".to_string(),
+        "!".to_string(),
+        "videorate".to_string(),
+        "!".to_string(),
+        "videoconvert".to_string(),
+        "!".to_string(),
+        "videoscale".to_string(),
+        "!".to_string(),
+        format!(
+            "video/x-raw,format=RGB,width={},height={},framerate={}/1",
+            stream.width, stream.height, fps
+        ),
+        "!".to_string(),
+        "fdsink".to_string(),
+        "fd=1".to_string(),
+    ];
+
+    log::info!("[Wayland] Command: {} {}", GST_LAUNCH, args.join(" "));
+    let mut command = Command::new(GST_LAUNCH);
+    command
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // The portal fd is received with CLOEXEC set; clear it so pipewiresrc inherits it
+    unsafe {
+        command.pre_exec(move || {
+            let flags = libc::fcntl(fd, libc::F_GETFD);
+            if flags < 0 || libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = command.spawn().map_err(|e| {
+        log::info!("[Wayland] Error: Failed to start gst-launch: {}", e);
+        format!(
+            "Failed to start {} (install GStreamer with the PipeWire plugin): {}",
+            GST_LAUNCH, e
+        )
+    })?;
+
+    if let Some(stderr) = child.stderr.take() {
+        thread::spawn(move || {
+            let reader = BufReader::new(stderr);
+            for line in reader.lines() {
+                if let Ok(line) = line {
+                    log::info!("[Wayland] gst-launch: {}", line);
+                }
+            }
+        });
+    }
+
+    Ok(child)
+}
@@ -1,9 +1,84 @@
+use crate::core::capture::CaptureRect;
+use crate::core::input_log::{InputEvent, LogEntry, SCHEMA_VERSION};
+use crate::core::record::MonitorInfo;
+use crate::core::redaction::{self, Bounds, PrivateRegion, Redactor, Span};
+use crate::utils::settings::RedactionSettings;
+use serde::Serialize;
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
+// Events waiting for the writer thread; past this, new events are dropped rather than
+// blocking the capture threads
+const QUEUE_CAPACITY: usize = 4_096;
+// How long written lines may sit in the buffer before reaching the file
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+// How often the file is synced to disk, so a crash loses at most this much of the log
+const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+// Key events held back for the next accessibility snapshot; past this many, or once the
+// first has waited this long, snapshots have stopped coming and they're written as they are
+const HELD_CAPACITY: usize = 1_000;
+const HELD_MAX_AGE: Duration = Duration::from_secs(10);
+
+// Signed milliseconds from `from` to `to`
+fn millis_between(from: Instant, to: Instant) -> i64 {
+    if to >= from {
+        (to - from).as_millis() as i64
+    } else {
+        -((from - to).as_millis() as i64)
+    }
+}
+
+/// How far the writer thread is behind the capture threads
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct LoggerStats {
+    // Events sent but not taken off the queue by the writer yet
+    pub queued: u64,
+    pub max_queued: u64,
+    pub logged: u64,
+    // Events dropped because the queue was full
+    pub dropped: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    logged: AtomicU64,
+    dropped: AtomicU64,
+    max_queued: AtomicU64,
+}
+
+enum Command {
+    // Input events are tagged with their monitor and made relative to the capture area
+    Input(LogEntry, Instant),
+    Event(LogEntry, Instant),
+    SetTimeOffset(i64),
+    SetMonitors(Vec<MonitorInfo>),
+    SetCaptureArea(Arc<Mutex<CaptureRect>>),
+    SetRedaction(RedactionSettings),
+    SetVideoOrigin(Instant),
+    EndSegment(Instant),
+    StartSegment(Instant),
+    Flush,
+    Close,
+}
+
+/// Writes input_log.jsonl. Besides the wall-clock `time`, every entry gets an `offset_ms`
+/// measured on a monotonic clock from the first video frame, so events stay aligned with
+/// the video across clock changes, ffmpeg startup latency and pauses. The format is
+/// described by `core::input_log`.
+///
+/// Events go through a channel to a writer thread that owns the file, so the input,
+/// dump-tree and ffmpeg threads never wait on the disk.
 pub struct Logger {
-    file: File,
+    sender: SyncSender<Command>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    counters: Arc<Counters>,
 }
 
 impl Logger {
@@ -16,11 +91,498 @@ impl Logger {
             .open(&log_path)
             .map_err(|e| format!("Failed to create log file: {}", e))?;
 
-        Ok(Logger { file })
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let counters = Arc::new(Counters::default());
+        let writer = LogWriter::new(file, session_dir, counters.clone());
+        let thread = thread::Builder::new()
+            .name("input-log-writer".to_string())
+            .spawn(move || writer.run(receiver))
+            .map_err(|e| format!("Failed to start log writer: {}", e))?;
+
+        Ok(Logger {
+            sender,
+            thread: Mutex::new(Some(thread)),
+            counters,
+        })
+    }
+
+    // Control messages are rare and must not be lost, so they wait for room in the queue
+    fn send(&self, command: Command) -> Result<(), String> {
+        self.sender
+            .send(command)
+            .map_err(|_| "Input log writer has stopped".to_string())
+    }
+
+    // Events never block: when the writer has fallen this far behind they are dropped
+    fn queue(&self, command: Command) -> Result<(), String> {
+        match self.sender.try_send(command) {
+            Ok(()) => {
+                let sent = self.counters.sent.fetch_add(1, Ordering::Relaxed) + 1;
+                let queued = sent.saturating_sub(self.counters.logged.load(Ordering::Relaxed));
+                self.counters
+                    .max_queued
+                    .fetch_max(queued, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => {
+                if self.counters.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                    log::warn!("[Logger] Writer is falling behind, dropping input events");
+                }
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => Err("Input log writer has stopped".to_string()),
+        }
+    }
+
+    pub fn stats(&self) -> LoggerStats {
+        let sent = self.counters.sent.load(Ordering::Relaxed);
+        let logged = self.counters.logged.load(Ordering::Relaxed);
+        LoggerStats {
+            queued: sent.saturating_sub(logged),
+            max_queued: self.counters.max_queued.load(Ordering::Relaxed),
+            logged,
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn set_time_offset(&self, offset_ms: i64) -> Result<(), String> {
+        self.send(Command::SetTimeOffset(offset_ms))
+    }
+
+    /// Anchors the current segment to the instant its first frame was captured. The first
+    /// call writes the `session_start` header; later calls in the same segment are ignored.
+    pub fn set_video_origin(&self, origin: Instant) -> Result<(), String> {
+        self.send(Command::SetVideoOrigin(origin))
+    }
+
+    /// Closes the current video segment when the recording is paused
+    pub fn end_segment(&self) -> Result<(), String> {
+        self.send(Command::EndSegment(Instant::now()))
+    }
+
+    /// Opens a new video segment when the recording resumes
+    pub fn start_segment(&self) -> Result<(), String> {
+        self.send(Command::StartSegment(Instant::now()))
+    }
+
+    /// Writes events still waiting for ffmpeg's first frame, assuming the segment started
+    /// when it was requested
+    pub fn flush(&self) -> Result<(), String> {
+        self.send(Command::Flush)
+    }
+
+    pub fn set_monitors(&self, monitors: Vec<MonitorInfo>) -> Result<(), String> {
+        self.send(Command::SetMonitors(monitors))
+    }
+
+    pub fn set_capture_area(&self, area: Arc<Mutex<CaptureRect>>) -> Result<(), String> {
+        self.send(Command::SetCaptureArea(area))
+    }
+
+    /// Enables redaction of keystrokes based on the `axtree` snapshots in the log
+    pub fn set_redaction(&self, settings: RedactionSettings) -> Result<(), String> {
+        self.send(Command::SetRedaction(settings))
+    }
+
+    /// Tags an input event with the index of the recorded monitor it landed on. Events
+    /// without a position (keys, scrolls, ...) use the cursor's last monitor.
+    /// When a window or region is recorded, positions are made relative to it and the
+    /// original ones kept as `screen_x`/`screen_y`.
+    pub fn log_input_event(&self, event: InputEvent) -> Result<(), String> {
+        self.queue(Command::Input(LogEntry::new(event), Instant::now()))
+    }
+
+    // #[cfg(not(target_os = "macos"))]
+    pub fn log_ffmpeg(&self, output: &str, is_stderr: bool) -> Result<(), String> {
+        let output = output.to_string();
+        let event = if is_stderr {
+            InputEvent::FfmpegStderr { output }
+        } else {
+            InputEvent::FfmpegStdout { output }
+        };
+
+        self.queue(Command::Event(LogEntry::new(event), Instant::now()))
+    }
+
+    /// Writes everything still queued, syncs the file and stops the writer thread
+    pub fn close(&self) -> Result<(), String> {
+        let Some(thread) = self.thread.lock().map_err(|e| e.to_string())?.take() else {
+            return Ok(());
+        };
+        self.send(Command::Close)?;
+        thread
+            .join()
+            .map_err(|_| "Input log writer panicked".to_string())
+    }
+}
+
+impl Drop for Logger {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            log::error!("[Logger] Failed to close input log: {}", e);
+        }
+    }
+}
+
+// State owned by the writer thread
+struct LogWriter {
+    file: BufWriter<File>,
+    session_dir: PathBuf,
+    counters: Arc<Counters>,
+    // Total paused time, subtracted from event times so they stay on the video timeline
+    time_offset_ms: i64,
+    // Video time at which the current segment starts
+    segment_start_ms: i64,
+    // Instant of the current segment's first frame; until ffmpeg reports it, events wait
+    // in `pending`
+    video_origin: Option<Instant>,
+    // Stand-in origin if ffmpeg never reports a frame for the segment
+    segment_requested_at: Instant,
+    paused: bool,
+    pending: Vec<(Instant, LogEntry)>,
+    header_written: bool,
+    // Recorded monitors, in the order listed in meta.json
+    monitors: Vec<MonitorInfo>,
+    cursor_monitor: Option<usize>,
+    // Window or region being recorded; mouse positions are logged relative to it
+    capture_area: Option<Arc<Mutex<CaptureRect>>>,
+    redactor: Option<Redactor>,
+    // Key events since the last snapshot while nothing is redacted, waiting for the next
+    // one to decide whether they belong to a redacted span
+    held: Vec<LogEntry>,
+    held_since: Option<Instant>,
+}
+
+impl LogWriter {
+    fn new(file: File, session_dir: PathBuf, counters: Arc<Counters>) -> Self {
+        LogWriter {
+            file: BufWriter::new(file),
+            session_dir,
+            counters,
+            time_offset_ms: 0,
+            segment_start_ms: 0,
+            video_origin: None,
+            segment_requested_at: Instant::now(),
+            paused: false,
+            pending: Vec::new(),
+            header_written: false,
+            monitors: Vec::new(),
+            cursor_monitor: None,
+            capture_area: None,
+            redactor: None,
+            held: Vec::new(),
+            held_since: None,
+        }
+    }
+
+    // Handles everything queued since the last wake-up in one batch, then flushes the
+    // buffer and syncs the file when they are due
+    fn run(mut self, receiver: Receiver<Command>) {
+        let mut flushed_at = Instant::now();
+        let mut synced_at = Instant::now();
+
+        loop {
+            let first = match receiver.recv_timeout(FLUSH_INTERVAL) {
+                Ok(command) => Some(command),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            let mut closing = false;
+            for command in first.into_iter().chain(receiver.try_iter()) {
+                if let Command::Close = command {
+                    closing = true;
+                    break;
+                }
+                if let Err(e) = self.handle(command) {
+                    log::error!("[Logger] {}", e);
+                }
+            }
+            if closing {
+                break;
+            }
+
+            if let Err(e) = self.release_stale_held(Instant::now()) {
+                log::error!("[Logger] {}", e);
+            }
+            if flushed_at.elapsed() >= FLUSH_INTERVAL {
+                if let Err(e) = self.file.flush() {
+                    log::error!("[Logger] Failed to flush input log: {}", e);
+                }
+                flushed_at = Instant::now();
+            }
+            if synced_at.elapsed() >= SYNC_INTERVAL {
+                if let Err(e) = self.file.get_ref().sync_data() {
+                    log::error!("[Logger] Failed to sync input log: {}", e);
+                }
+                synced_at = Instant::now();
+            }
+        }
+
+        if let Err(e) = self.close() {
+            log::error!("[Logger] {}", e);
+        }
+    }
+
+    fn handle(&mut self, command: Command) -> Result<(), String> {
+        match command {
+            Command::Input(entry, logged_at) => {
+                self.counters.logged.fetch_add(1, Ordering::Relaxed);
+                self.log_input_event(entry, logged_at)
+            }
+            Command::Event(entry, logged_at) => {
+                self.counters.logged.fetch_add(1, Ordering::Relaxed);
+                self.log_event(entry, logged_at)
+            }
+            Command::SetTimeOffset(offset_ms) => {
+                self.time_offset_ms = offset_ms;
+                Ok(())
+            }
+            Command::SetMonitors(monitors) => {
+                self.monitors = monitors;
+                self.cursor_monitor = None;
+                Ok(())
+            }
+            Command::SetCaptureArea(area) => {
+                self.capture_area = Some(area);
+                Ok(())
+            }
+            Command::SetRedaction(settings) => {
+                self.redactor = Some(Redactor::new(settings));
+                Ok(())
+            }
+            Command::SetVideoOrigin(origin) => self.set_video_origin(origin),
+            Command::EndSegment(ended_at) => self.end_segment(ended_at),
+            Command::StartSegment(requested_at) => {
+                self.paused = false;
+                self.segment_requested_at = requested_at;
+                Ok(())
+            }
+            Command::Flush => self.flush(),
+            Command::Close => Ok(()),
+        }
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        self.flush()?;
+        // No snapshot came after these, so nothing says they were private
+        self.release_held()?;
+        // A span still open at the end runs to the end of the video
+        let end_ms = self.current_offset(Instant::now());
+        if let Some(span) = self.redactor.as_mut().and_then(|r| r.finish(end_ms)) {
+            self.save_private_range(span)?;
+        }
+        self.file
+            .flush()
+            .map_err(|e| format!("Failed to flush input log: {}", e))?;
+        self.file
+            .get_ref()
+            .sync_all()
+            .map_err(|e| format!("Failed to sync input log: {}", e))?;
+
+        log::info!(
+            "[Logger] Input log closed: {} events logged, {} dropped, at most {} queued",
+            self.counters.logged.load(Ordering::Relaxed),
+            self.counters.dropped.load(Ordering::Relaxed),
+            self.counters.max_queued.load(Ordering::Relaxed)
+        );
+        Ok(())
+    }
+
+    fn set_video_origin(&mut self, origin: Instant) -> Result<(), String> {
+        if self.paused || self.video_origin.is_some() {
+            return Ok(());
+        }
+
+        if !self.header_written {
+            let started_at = chrono::Local::now()
+                - chrono::Duration::milliseconds(millis_between(origin, Instant::now()));
+            self.write_line(&LogEntry {
+                event: InputEvent::SessionStart {
+                    schema_version: SCHEMA_VERSION,
+                    started_at: started_at.to_rfc3339(),
+                    clock: "monotonic".to_string(),
+                },
+                time: started_at.timestamp_millis(),
+                offset_ms: Some(0),
+                monitor: None,
+            })?;
+            self.header_written = true;
+        }
+
+        self.video_origin = Some(origin);
+        for (logged_at, entry) in std::mem::take(&mut self.pending) {
+            self.write_event(entry, logged_at)?;
+        }
+        Ok(())
+    }
+
+    fn end_segment(&mut self, ended_at: Instant) -> Result<(), String> {
+        self.flush()?;
+        if let Some(origin) = self.video_origin.take() {
+            self.segment_start_ms += millis_between(origin, ended_at).max(0);
+        }
+        self.paused = true;
+        Ok(())
     }
 
-    pub fn log_event(&mut self, event: serde_json::Value) -> Result<(), String> {
-        let json = serde_json::to_string(&event)
+    fn flush(&mut self) -> Result<(), String> {
+        if !self.paused && self.video_origin.is_none() {
+            self.set_video_origin(self.segment_requested_at)?;
+        }
+        Ok(())
+    }
+
+    fn log_input_event(&mut self, mut entry: LogEntry, logged_at: Instant) -> Result<(), String> {
+        if let InputEvent::Axtree(_) = entry.event {
+            return self.log_event(entry, logged_at);
+        }
+
+        let position = entry.event.position();
+
+        if !self.monitors.is_empty() {
+            if let Some((x, y)) = position {
+                self.cursor_monitor = self.monitors.iter().position(|m| m.contains(x, y));
+            }
+            entry.monitor = self.cursor_monitor;
+        }
+
+        if let (Some(_), Some(area)) = (position, &self.capture_area) {
+            let area = *area.lock().map_err(|e| e.to_string())?;
+            entry.event.make_relative((area.x as f64, area.y as f64));
+        }
+
+        self.log_event(entry, logged_at)
+    }
+
+    fn log_event(&mut self, mut entry: LogEntry, logged_at: Instant) -> Result<(), String> {
+        entry.time -= self.time_offset_ms;
+
+        if self.paused || self.video_origin.is_some() {
+            self.write_event(entry, logged_at)
+        } else {
+            self.pending.push((logged_at, entry));
+            Ok(())
+        }
+    }
+
+    // Events logged while paused sit at the cut between the two segments
+    fn current_offset(&self, logged_at: Instant) -> i64 {
+        match self.video_origin {
+            Some(origin) => self.segment_start_ms + millis_between(origin, logged_at),
+            None => self.segment_start_ms,
+        }
+    }
+
+    fn write_event(&mut self, mut entry: LogEntry, logged_at: Instant) -> Result<(), String> {
+        let offset_ms = self.current_offset(logged_at);
+        entry.offset_ms = Some(offset_ms);
+        for entry in self.redact(entry)? {
+            self.write_line(&entry)?;
+        }
+        Ok(())
+    }
+
+    // Applies live redaction and returns the entries that can be written now. A span found
+    // by a snapshot starts at the previous one, so while nothing is redacted, key events are
+    // held until the next snapshot tells whether they were typed into something private.
+    // Everything else is written right away, ahead of the keys held before it.
+    fn redact(&mut self, entry: LogEntry) -> Result<Vec<LogEntry>, String> {
+        let Some(redactor) = self.redactor.as_mut() else {
+            return Ok(vec![entry]);
+        };
+        let offset_ms = entry.offset_ms.unwrap_or(self.segment_start_ms);
+
+        let InputEvent::Axtree(snapshot) = &entry.event else {
+            if redactor.active().is_some() || !is_key(&entry.event) {
+                return Ok(redact_keys(redactor, entry).into_iter().collect());
+            }
+            self.held_since.get_or_insert_with(Instant::now);
+            self.held.push(entry);
+            if self.held.len() >= HELD_CAPACITY {
+                log::warn!("[Logger] No accessibility snapshot for a while, writing held keys");
+                self.release_held()?;
+            }
+            return Ok(Vec::new());
+        };
+
+        let closed = redactor.observe(snapshot, offset_ms);
+        // The tree of a denylisted app would show what the keystrokes did
+        let keep = !matches!(redactor.active(), Some(redaction::Sensitive::App(_)));
+        self.held_since = None;
+        let mut entries: Vec<LogEntry> = std::mem::take(&mut self.held)
+            .into_iter()
+            .filter_map(|held| redact_keys(redactor, held))
+            .collect();
+        entries.extend(keep.then_some(entry));
+        if let Some(span) = closed {
+            self.save_private_range(span)?;
+        }
+        Ok(entries)
+    }
+
+    fn release_held(&mut self) -> Result<(), String> {
+        self.held_since = None;
+        for entry in std::mem::take(&mut self.held) {
+            self.write_line(&entry)?;
+        }
+        Ok(())
+    }
+
+    // Keeps held keys from waiting on snapshots that aren't coming
+    fn release_stale_held(&mut self, now: Instant) -> Result<(), String> {
+        match self.held_since {
+            Some(since) if now.duration_since(since) >= HELD_MAX_AGE => {
+                log::warn!("[Logger] No accessibility snapshot for a while, writing held keys");
+                self.release_held()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn save_private_range(&self, span: Span) -> Result<(), String> {
+        let regions: Vec<PrivateRegion> = match span.bounds {
+            Some(bounds) => self.private_region(bounds)?.into_iter().collect(),
+            None => Vec::new(),
+        };
+        redaction::append_private_range(
+            &self.session_dir,
+            span.start_ms,
+            span.end_ms,
+            &span.sensitive.reason(),
+            &regions,
+        )
+    }
+
+    // Moves screen bounds into the recorded area, the way mouse positions are logged.
+    // `None` when they aren't on any recorded monitor.
+    fn private_region(&self, bounds: Bounds) -> Result<Option<PrivateRegion>, String> {
+        let (origin, monitor) = match &self.capture_area {
+            Some(area) => {
+                let area = *area.lock().map_err(|e| e.to_string())?;
+                ((area.x, area.y), None)
+            }
+            None => {
+                let (x, y) = bounds.center();
+                match self.monitors.iter().position(|m| m.contains(x, y)) {
+                    Some(index) => (self.monitors[index].origin(), Some(index)),
+                    None if self.monitors.is_empty() => ((0, 0), None),
+                    None => return Ok(None),
+                }
+            }
+        };
+        Ok(Some(PrivateRegion {
+            x: bounds.x - origin.0 as f64,
+            y: bounds.y - origin.1 as f64,
+            w: bounds.width,
+            h: bounds.height,
+            monitor,
+            style: Default::default(),
+        }))
+    }
+
+    fn write_line(&mut self, entry: &LogEntry) -> Result<(), String> {
+        let json = serde_json::to_string(entry)
             .map_err(|e| format!("Failed to serialize event: {}", e))?;
 
         writeln!(self.file, "{}", json)
@@ -28,17 +590,238 @@ impl Logger {
 
         Ok(())
     }
+}
 
-    // #[cfg(not(target_os = "macos"))]
-    pub fn log_ffmpeg(&mut self, output: &str, is_stderr: bool) -> Result<(), String> {
-        let event = serde_json::json!({
-            "event": if is_stderr { "ffmpeg_stderr" } else { "ffmpeg_stdout" },
-            "data": {
-                "output": output
-            },
-            "time": chrono::Local::now().timestamp_millis()
-        });
-
-        self.log_event(event)
+fn is_key(event: &InputEvent) -> bool {
+    matches!(
+        event,
+        InputEvent::Keydown { .. } | InputEvent::Keyup { .. } | InputEvent::Shortcut { .. }
+    )
+}
+
+// While a span is open, the first keystroke becomes its `redacted_keys` marker and the
+// others are left out; everything else passes through
+fn redact_keys(redactor: &mut Redactor, mut entry: LogEntry) -> Option<LogEntry> {
+    let Some(sensitive) = redactor.active() else {
+        return Some(entry);
+    };
+    match entry.event {
+        InputEvent::Keydown { .. } => {
+            let reason = sensitive.reason();
+            if !redactor.take_marker() {
+                return None;
+            }
+            entry.event = InputEvent::RedactedKeys { reason };
+            Some(entry)
+        }
+        InputEvent::Keyup { .. } | InputEvent::Shortcut { .. } => None,
+        _ => Some(entry),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    // A writer with redaction on for a fresh session directory, its video started at the
+    // returned instant
+    fn writer(name: &str) -> (LogWriter, PathBuf, Instant) {
+        let dir = std::env::temp_dir().join(format!("logger-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = File::create(dir.join("input_log.jsonl")).unwrap();
+
+        let mut writer = LogWriter::new(file, dir.clone(), Arc::new(Counters::default()));
+        let origin = Instant::now();
+        writer
+            .handle(Command::SetRedaction(RedactionSettings::default()))
+            .unwrap();
+        writer.handle(Command::SetVideoOrigin(origin)).unwrap();
+        (writer, dir, origin)
+    }
+
+    fn log(writer: &mut LogWriter, origin: Instant, at_ms: u64, event: InputEvent) {
+        let logged_at = origin + Duration::from_millis(at_ms);
+        writer
+            .handle(Command::Input(LogEntry::new(event), logged_at))
+            .unwrap();
+    }
+
+    fn click() -> InputEvent {
+        InputEvent::Mousedown {
+            button: "left".to_string(),
+            x: Some(50.0),
+            y: Some(60.0),
+            screen_x: None,
+            screen_y: None,
+        }
+    }
+
+    fn keydown(key: &str) -> InputEvent {
+        InputEvent::Keydown {
+            key: key.to_string(),
+            modifiers: Vec::new(),
+            scancode: None,
+            text: Some(key.to_string()),
+        }
+    }
+
+    // A browser window with a focused text field, a password field when `secure`
+    fn snapshot(secure: bool) -> InputEvent {
+        InputEvent::Axtree(json!({
+            "tree": [{
+                "name": "Browser",
+                "role": "application",
+                "children": [{
+                    "name": "Field",
+                    "role": if secure { "password text" } else { "text" },
+                    "focused": true,
+                    "bbox": { "x": 10.0, "y": 20.0, "width": 200.0, "height": 30.0 }
+                }]
+            }]
+        }))
+    }
+
+    fn read_json(path: PathBuf) -> Vec<Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn events(lines: &[Value]) -> Vec<(&str, i64)> {
+        lines
+            .iter()
+            .map(|line| {
+                (
+                    line["event"].as_str().unwrap(),
+                    line["offset_ms"].as_i64().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keys_typed_before_the_first_detecting_snapshot_are_redacted() {
+        let (mut writer, dir, origin) = writer("before-first-snapshot");
+        log(&mut writer, origin, 100, keydown("h"));
+        log(&mut writer, origin, 200, keydown("i"));
+        log(&mut writer, origin, 2000, snapshot(true));
+        log(&mut writer, origin, 2100, keydown("x"));
+        log(&mut writer, origin, 4000, snapshot(false));
+        log(&mut writer, origin, 4100, keydown("y"));
+        writer.close().unwrap();
+
+        let lines = read_json(dir.join("input_log.jsonl"));
+        assert_eq!(
+            events(&lines),
+            [
+                ("session_start", 0),
+                ("redacted_keys", 100),
+                ("axtree", 2000),
+                ("axtree", 4000),
+                ("keydown", 4100),
+            ]
+        );
+        assert_eq!(lines[1]["data"]["reason"], "secure_field");
+        assert_eq!(lines[4]["data"]["key"], "y");
+
+        let ranges: Vec<Value> = serde_json::from_str(
+            &std::fs::read_to_string(dir.join("private_ranges.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(
+            (&ranges[0]["start"], &ranges[0]["end"]),
+            (&json!(0), &json!(4000))
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keys_before_a_clean_snapshot_are_written_in_order() {
+        let (mut writer, dir, origin) = writer("clean-snapshot");
+        log(&mut writer, origin, 100, keydown("a"));
+        log(&mut writer, origin, 300, keydown("b"));
+        log(&mut writer, origin, 2000, snapshot(false));
+        writer.close().unwrap();
+
+        let lines = read_json(dir.join("input_log.jsonl"));
+        assert_eq!(
+            events(&lines),
+            [
+                ("session_start", 0),
+                ("keydown", 100),
+                ("keydown", 300),
+                ("axtree", 2000),
+            ]
+        );
+        assert_eq!(lines[1]["data"]["key"], "a");
+        assert!(!dir.join("private_ranges.json").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_keys_wait_for_the_next_snapshot() {
+        let (mut writer, dir, origin) = writer("only-keys-held");
+        log(&mut writer, origin, 100, keydown("p"));
+        log(&mut writer, origin, 200, click());
+        writer.file.flush().unwrap();
+
+        let lines = read_json(dir.join("input_log.jsonl"));
+        assert_eq!(events(&lines), [("session_start", 0), ("mousedown", 200)]);
+
+        log(&mut writer, origin, 2000, snapshot(true));
+        writer.close().unwrap();
+        let lines = read_json(dir.join("input_log.jsonl"));
+        assert_eq!(
+            events(&lines),
+            [
+                ("session_start", 0),
+                ("mousedown", 200),
+                ("redacted_keys", 100),
+                ("axtree", 2000),
+            ]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn held_keys_are_written_once_snapshots_stop_coming() {
+        let (mut writer, dir, origin) = writer("stale-held-keys");
+        log(&mut writer, origin, 100, keydown("q"));
+        writer.release_stale_held(Instant::now()).unwrap();
+        writer.file.flush().unwrap();
+        assert_eq!(events(&read_json(dir.join("input_log.jsonl"))).len(), 1);
+
+        writer
+            .release_stale_held(Instant::now() + HELD_MAX_AGE)
+            .unwrap();
+        writer.file.flush().unwrap();
+        let lines = read_json(dir.join("input_log.jsonl"));
+        assert_eq!(events(&lines), [("session_start", 0), ("keydown", 100)]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn snapshots_of_denylisted_apps_are_left_out() {
+        let (mut writer, dir, origin) = writer("denylisted-app");
+        let password_manager = InputEvent::Axtree(json!({
+            "tree": [{ "name": "1Password", "role": "application", "focused": true }]
+        }));
+        log(&mut writer, origin, 100, keydown("s"));
+        log(&mut writer, origin, 2000, password_manager);
+        log(&mut writer, origin, 2500, keydown("t"));
+        writer.close().unwrap();
+
+        let lines = read_json(dir.join("input_log.jsonl"));
+        assert_eq!(
+            events(&lines),
+            [("session_start", 0), ("redacted_keys", 100)]
+        );
+        assert_eq!(lines[1]["data"]["reason"], "denylisted_app:1Password");
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -4,3 +4,4 @@ pub mod github_release;
 pub mod logger;
 pub mod permissions;
 pub mod settings;
+pub mod storage;
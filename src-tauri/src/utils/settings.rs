@@ -11,6 +11,345 @@ use tauri::{AppHandle, Manager};
 pub struct Settings {
     pub upload_confirmed: bool,
     pub onboarding_complete: bool,
+    #[serde(default)]
+    pub encoding: EncodingProfile,
+    #[serde(default)]
+    pub limits: RecordingLimits,
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    // Folder that holds `recordings/`; the app data folder when unset
+    #[serde(default)]
+    pub storage_root: Option<String>,
+    #[serde(default)]
+    pub input_sampling: InputSampling,
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+    #[serde(default)]
+    pub pii_scrubbing: PiiScrubbing,
+}
+
+/// Video encoders the recorder knows how to configure
+pub const SUPPORTED_CODECS: &[&str] = &["libx264", "libx265", "libvpx-vp9"];
+
+/// x264/x265 preset names; VP9 maps them onto its deadline/cpu-used options
+pub const SUPPORTED_PRESETS: &[&str] = &[
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EncodingProfile {
+    pub codec: String,
+    pub preset: String,
+    pub crf: u32,
+    pub fps: u32,
+    // Recordings larger than this are downscaled, keeping the aspect ratio
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+}
+
+impl Default for EncodingProfile {
+    fn default() -> Self {
+        Self {
+            codec: "libx264".to_string(),
+            preset: "ultrafast".to_string(),
+            crf: 23,
+            fps: 30,
+            max_width: None,
+            max_height: None,
+        }
+    }
+}
+
+impl EncodingProfile {
+    /// Checks the profile values; whether ffmpeg has the encoder is checked separately
+    pub fn validate(&self) -> Result<(), String> {
+        if !SUPPORTED_CODECS.contains(&self.codec.as_str()) {
+            return Err(format!(
+                "Unsupported codec '{}', expected one of: {}",
+                self.codec,
+                SUPPORTED_CODECS.join(", ")
+            ));
+        }
+
+        if !SUPPORTED_PRESETS.contains(&self.preset.as_str()) {
+            return Err(format!(
+                "Unsupported preset '{}', expected one of: {}",
+                self.preset,
+                SUPPORTED_PRESETS.join(", ")
+            ));
+        }
+
+        let max_crf = if self.codec == "libvpx-vp9" { 63 } else { 51 };
+        if self.crf > max_crf {
+            return Err(format!(
+                "CRF {} is out of range for {} (0-{})",
+                self.crf, self.codec, max_crf
+            ));
+        }
+
+        if self.fps == 0 || self.fps > 120 {
+            return Err(format!("Frame rate {} is out of range (1-120)", self.fps));
+        }
+
+        for (name, value) in [("width", self.max_width), ("height", self.max_height)] {
+            if let Some(value) = value {
+                if value < 16 || value % 2 != 0 {
+                    return Err(format!(
+                        "Maximum {} must be an even number of at least 16 pixels",
+                        name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Guards against a forgotten session filling the drive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RecordingLimits {
+    // A `recording-disk-warning` event is emitted once free space drops below this
+    pub warn_free_space_mb: u64,
+    // Recordings refuse to start, and are stopped, below this much free space
+    pub min_free_space_mb: u64,
+    pub max_duration_minutes: Option<u64>,
+    // Total size of the session directory, all monitors included
+    pub max_size_mb: Option<u64>,
+}
+
+impl Default for RecordingLimits {
+    fn default() -> Self {
+        Self {
+            warn_free_space_mb: 5 * 1024,
+            min_free_space_mb: 1024,
+            max_duration_minutes: None,
+            max_size_mb: None,
+        }
+    }
+}
+
+impl RecordingLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.warn_free_space_mb < self.min_free_space_mb {
+            return Err(format!(
+                "Free space warning ({} MB) must not be below the minimum free space ({} MB)",
+                self.warn_free_space_mb, self.min_free_space_mb
+            ));
+        }
+
+        if self.max_duration_minutes == Some(0) {
+            return Err("Maximum duration must be at least one minute".to_string());
+        }
+
+        if self.max_size_mb == Some(0) {
+            return Err("Maximum size must be at least one megabyte".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Which finished recordings are deleted automatically; every rule is off by default
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    // Number of most recent recordings to keep
+    pub keep_last: Option<usize>,
+    pub max_age_days: Option<u32>,
+    pub delete_after_upload: bool,
+    // Total size of the recordings folder; the oldest recordings go first
+    pub max_total_gb: Option<f64>,
+}
+
+impl RetentionPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_age_days == Some(0) {
+            return Err("Maximum age must be at least one day".to_string());
+        }
+
+        if let Some(max_total_gb) = self.max_total_gb {
+            if !max_total_gb.is_finite() || max_total_gb <= 0.0 {
+                return Err(format!("Invalid storage quota of {} GB", max_total_gb));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Sampling of mouse moves for one destination; other input events are never dropped
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct MoveSampling {
+    // Unlimited when unset
+    pub max_hz: Option<u32>,
+    // Moves closer than this to the last kept position are dropped
+    pub min_distance_px: f64,
+}
+
+impl MoveSampling {
+    fn validate(&self, name: &str) -> Result<(), String> {
+        if self.max_hz == Some(0) {
+            return Err(format!(
+                "Mouse move rate for the {} must be at least 1 Hz",
+                name
+            ));
+        }
+
+        if !self.min_distance_px.is_finite() || self.min_distance_px < 0.0 {
+            return Err(format!(
+                "Invalid minimum mouse move distance of {} px for the {}",
+                self.min_distance_px, name
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// How many mouse moves go to input_log.jsonl and to the `input-event` stream
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct InputSampling {
+    pub log: MoveSampling,
+    // The UI only shows a preview, so it gets far fewer
+    pub emit: MoveSampling,
+}
+
+impl Default for InputSampling {
+    fn default() -> Self {
+        Self {
+            log: MoveSampling {
+                max_hz: Some(120),
+                min_distance_px: 0.0,
+            },
+            emit: MoveSampling {
+                max_hz: Some(30),
+                min_distance_px: 2.0,
+            },
+        }
+    }
+}
+
+impl InputSampling {
+    pub fn validate(&self) -> Result<(), String> {
+        self.log.validate("log")?;
+        self.emit.validate("UI")
+    }
+}
+
+/// What exporting a recording does with input log events inside private ranges
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivateEventMode {
+    /// Keep the events but scrub their content, between `private_start`/`private_end`
+    #[default]
+    Redact,
+    /// Leave the events out of the exported log
+    Drop,
+}
+
+/// Keystrokes typed into password fields or denylisted apps are left out of the input log
+/// while recording, and the time is added to the session's private ranges
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RedactionSettings {
+    pub enabled: bool,
+    pub secure_fields: bool,
+    // Matched case-insensitively against the focused app, window and page names
+    pub denylisted_apps: Vec<String>,
+    // Applies to every private range on export, including the ones marked by hand
+    pub private_events: PrivateEventMode,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            secure_fields: true,
+            denylisted_apps: [
+                "1Password",
+                "Bitwarden",
+                "Dashlane",
+                "KeePass",
+                "Keychain Access",
+                "LastPass",
+                "Proton Pass",
+            ]
+            .iter()
+            .map(|app| app.to_string())
+            .collect(),
+            private_events: PrivateEventMode::default(),
+        }
+    }
+}
+
+impl RedactionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.denylisted_apps.iter().any(|app| app.trim().is_empty()) {
+            return Err("Denylisted app names must not be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Personal data replaced with typed placeholders in accessibility tree snapshots before
+/// they reach the input log
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PiiScrubbing {
+    pub enabled: bool,
+    pub emails: bool,
+    pub phone_numbers: bool,
+    // Runs of 13 to 19 digits that pass the Luhn check
+    pub card_numbers: bool,
+    // API keys, JWTs and long random-looking strings
+    pub tokens: bool,
+    pub custom_patterns: Vec<CustomPattern>,
+}
+
+/// A user-defined rule; its matches are replaced with `[NAME]`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomPattern {
+    pub name: String,
+    pub pattern: String,
+}
+
+impl Default for PiiScrubbing {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            emails: true,
+            phone_numbers: true,
+            card_numbers: true,
+            tokens: true,
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+impl PiiScrubbing {
+    pub fn validate(&self) -> Result<(), String> {
+        for custom in &self.custom_patterns {
+            if custom.name.trim().is_empty() {
+                return Err("Custom pattern names must not be empty".to_string());
+            }
+            regex::Regex::new(&custom.pattern)
+                .map_err(|e| format!("Invalid pattern for {}: {}", custom.name, e))?;
+        }
+        Ok(())
+    }
 }
 
 impl Settings {
@@ -61,6 +400,60 @@ impl Settings {
                                         }
                                     }
 
+                                    if let Some(encoding) = json.get("encoding") {
+                                        if let Ok(value) = serde_json::from_value::<EncodingProfile>(
+                                            encoding.clone(),
+                                        ) {
+                                            settings.encoding = value;
+                                        }
+                                    }
+
+                                    if let Some(limits) = json.get("limits") {
+                                        if let Ok(value) = serde_json::from_value::<RecordingLimits>(
+                                            limits.clone(),
+                                        ) {
+                                            settings.limits = value;
+                                        }
+                                    }
+
+                                    if let Some(retention) = json.get("retention") {
+                                        if let Ok(value) = serde_json::from_value::<RetentionPolicy>(
+                                            retention.clone(),
+                                        ) {
+                                            settings.retention = value;
+                                        }
+                                    }
+
+                                    if let Some(storage_root) = json.get("storage_root") {
+                                        if let Some(value) = storage_root.as_str() {
+                                            settings.storage_root = Some(value.to_string());
+                                        }
+                                    }
+
+                                    if let Some(input_sampling) = json.get("input_sampling") {
+                                        if let Ok(value) = serde_json::from_value::<InputSampling>(
+                                            input_sampling.clone(),
+                                        ) {
+                                            settings.input_sampling = value;
+                                        }
+                                    }
+
+                                    if let Some(redaction) = json.get("redaction") {
+                                        if let Ok(value) = serde_json::from_value::<RedactionSettings>(
+                                            redaction.clone(),
+                                        ) {
+                                            settings.redaction = value;
+                                        }
+                                    }
+
+                                    if let Some(pii_scrubbing) = json.get("pii_scrubbing") {
+                                        if let Ok(value) = serde_json::from_value::<PiiScrubbing>(
+                                            pii_scrubbing.clone(),
+                                        ) {
+                                            settings.pii_scrubbing = value;
+                                        }
+                                    }
+
                                     info!("[Settings] Manually recovered partial settings");
                                     return settings;
                                 }
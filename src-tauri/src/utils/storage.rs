@@ -0,0 +1,216 @@
+use crate::utils::settings::Settings;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+
+// The configured storage root, or the app data folder
+fn storage_root(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    match Settings::load(app).storage_root {
+        Some(root) => Ok(PathBuf::from(root)),
+        None => app
+            .path()
+            .app_local_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e)),
+    }
+}
+
+/// Folder holding one directory per recording session: `<storage_root>/recordings` when a
+/// storage root is configured, `app_local_data_dir()/recordings` otherwise
+pub fn recordings_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(storage_root(app)?.join("recordings"))
+}
+
+/// Where recording archives are built, next to `recordings/` so they land on the same disk
+/// without being mistaken for a session
+pub fn exports_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(storage_root(app)?.join("exports"))
+}
+
+/// Directory of a single recording session
+pub fn recording_dir(app: &tauri::AppHandle, recording_id: &str) -> Result<PathBuf, String> {
+    Ok(recordings_dir(app)?.join(recording_id))
+}
+
+// Every file below `dir`, relative to it
+fn list_files(dir: &Path, base: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))? {
+        let path = entry
+            .map_err(|e| format!("Failed to read directory entry: {}", e))?
+            .path();
+        if path.is_dir() {
+            list_files(&path, base, files)?;
+        } else {
+            files.push(path.strip_prefix(base).unwrap().to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+// Byte-for-byte comparison, so a truncated or corrupted copy is never mistaken for the original
+fn files_match(a: &Path, b: &Path) -> Result<bool, String> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+    };
+    let (mut a, mut b) = (open(a)?, open(b)?);
+    let mut buf_a = vec![0u8; 64 * 1024];
+    let mut buf_b = vec![0u8; 64 * 1024];
+
+    loop {
+        let read_a = a
+            .read(&mut buf_a)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if read_a == 0 {
+            return Ok(b
+                .read(&mut buf_b[..1])
+                .map_err(|e| format!("Failed to read file: {}", e))?
+                == 0);
+        }
+        // Fails at the end of a shorter copy
+        if b.read_exact(&mut buf_b[..read_a]).is_err() || buf_a[..read_a] != buf_b[..read_a] {
+            return Ok(false);
+        }
+    }
+}
+
+// Copies one session and checks every file against its source
+fn copy_session(source: &Path, destination: &Path) -> Result<u64, String> {
+    let mut files = Vec::new();
+    list_files(source, source, &mut files)?;
+
+    let mut copied = 0;
+    for file in &files {
+        let target = destination.join(file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        copied += fs::copy(source.join(file), &target)
+            .map_err(|e| format!("Failed to copy {}: {}", file.display(), e))?;
+    }
+
+    for file in &files {
+        if !files_match(&source.join(file), &destination.join(file))? {
+            return Err(format!(
+                "Verification failed for {}",
+                destination.join(file).display()
+            ));
+        }
+    }
+
+    Ok(copied)
+}
+
+/// Moves every session to `<storage_root>/recordings` (back to the app data folder when
+/// `storage_root` is `None`) and stores the new root in the settings.
+///
+/// Sessions are copied and verified first; the originals are only deleted once everything
+/// arrived intact, so a failure leaves the recordings where they were. Emits
+/// `recordings-move-progress` after each session.
+pub fn move_recordings(
+    app: &tauri::AppHandle,
+    storage_root: Option<String>,
+) -> Result<String, String> {
+    let source = recordings_dir(app)?;
+    let destination = match &storage_root {
+        Some(root) => PathBuf::from(root).join("recordings"),
+        None => app
+            .path()
+            .app_local_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?
+            .join("recordings"),
+    };
+
+    fs::create_dir_all(&destination)
+        .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+    let canonical_destination = destination
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", destination.display(), e))?;
+    if let Ok(canonical_source) = source.canonicalize() {
+        if canonical_source == canonical_destination {
+            return Err("Recordings are already stored there".to_string());
+        }
+        if canonical_destination.starts_with(&canonical_source) {
+            return Err("Recordings cannot be moved into their own folder".to_string());
+        }
+    }
+
+    let mut sessions = Vec::new();
+    if source.exists() {
+        for entry in fs::read_dir(&source)
+            .map_err(|e| format!("Failed to read recordings directory: {}", e))?
+        {
+            let path = entry
+                .map_err(|e| format!("Failed to read directory entry: {}", e))?
+                .path();
+            if path.is_dir() {
+                sessions.push(path);
+            }
+        }
+    }
+
+    for session in &sessions {
+        let target = destination.join(session.file_name().unwrap());
+        if target.exists() {
+            return Err(format!(
+                "{} already contains a recording named {}",
+                destination.display(),
+                session.file_name().unwrap().to_string_lossy()
+            ));
+        }
+    }
+
+    log::info!(
+        "[Storage] Moving {} recording(s) from {} to {}",
+        sessions.len(),
+        source.display(),
+        destination.display()
+    );
+
+    let mut copied = Vec::new();
+    let mut bytes_copied = 0;
+    for (index, session) in sessions.iter().enumerate() {
+        let id = session.file_name().unwrap().to_string_lossy().to_string();
+        let target = destination.join(&id);
+        match copy_session(session, &target) {
+            Ok(bytes) => bytes_copied += bytes,
+            Err(e) => {
+                log::error!("[Storage] Failed to move recording {}: {}", id, e);
+                let _ = fs::remove_dir_all(&target);
+                for target in copied {
+                    let _ = fs::remove_dir_all(target);
+                }
+                return Err(format!("Failed to move recording {}: {}", id, e));
+            }
+        }
+        copied.push(target);
+
+        let _ = app.emit(
+            "recordings-move-progress",
+            serde_json::json!({
+                "id": id,
+                "completed": index + 1,
+                "total": sessions.len(),
+                "bytes_copied": bytes_copied
+            }),
+        );
+    }
+
+    let mut settings = Settings::load(app);
+    settings.storage_root = storage_root;
+    settings.save(app)?;
+
+    for session in &sessions {
+        if let Err(e) = fs::remove_dir_all(session) {
+            log::warn!(
+                "[Storage] Failed to remove old copy {}: {}",
+                session.display(),
+                e
+            );
+        }
+    }
+
+    log::info!("[Storage] Recordings moved to {}", destination.display());
+    Ok(destination.to_string_lossy().to_string())
+}
@@ -195,4 +195,24 @@ export class ChunkedUploader {
     this.totalChunks = chunks.length;
     return { chunks, totalChunks: chunks.length };
   }
+
+  /**
+   * Splits a file of the given size into byte ranges of the chunk size, for files that are
+   * read one chunk at a time instead of being loaded as a whole
+   * @param totalBytes Size of the file
+   * @returns Array of [start, end) ranges
+   */
+  planChunks(totalBytes: number): { ranges: Array<[number, number]>; totalChunks: number } {
+    const ranges: Array<[number, number]> = [];
+    let start = 0;
+
+    while (start < totalBytes) {
+      const end = Math.min(start + this.chunkSize, totalBytes);
+      ranges.push([start, end]);
+      start = end;
+    }
+
+    this.totalChunks = ranges.length;
+    return { ranges, totalChunks: ranges.length };
+  }
 }
@@ -54,6 +54,8 @@ export interface LocalRecording {
   quest?: Quest & { reward?: { time: number; max_reward: number } };
   submission?: SubmissionStatus;
   location?: 'local' | 'database';
+  pinned?: boolean;
+  uploaded_at?: string;
 }
 
 export interface ApiRecording {
@@ -175,7 +177,9 @@ export enum RecordingState {
   starting = 'starting',
   recording = 'recording',
   saving = 'saving',
-  saved = 'saved'
+  saved = 'saved',
+  paused = 'paused',
+  error = 'error'
 }
 
 export interface Screen {
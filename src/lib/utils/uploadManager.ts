@@ -223,26 +223,26 @@ export class UploadManager {
         progress: 0
       });
 
-      // Get zip file as bytes
-      const zipBytes = await invoke<number[]>('create_recording_zip', { recordingId });
-
-      // Convert to Blob
-      const zipBlob = new Blob([Uint8Array.from(zipBytes)], { type: 'application/zip' });
+      // Build the zip on disk; it is read back one chunk at a time so it never has to fit
+      // in memory
+      const zip = await invoke<{ path: string; size: number }>('create_recording_zip_file', {
+        recordingId
+      });
 
       // Update progress after zipping
       this.updateQueue(recordingId, {
         status: 'uploading',
         name,
         progress: 10,
-        totalBytes: zipBlob.size,
+        totalBytes: zip.size,
         uploadedBytes: 0
       });
 
       // Initialize chunked uploader
       const uploader = new ChunkedUploader();
 
-      // Split the blob into chunks
-      const { chunks, totalChunks } = uploader.splitIntoChunks(zipBlob);
+      // Split the file into chunks
+      const { ranges, totalChunks } = uploader.planChunks(zip.size);
 
       // Update queue with total chunks info
       this.updateQueue(recordingId, {
@@ -261,9 +261,9 @@ export class UploadManager {
       });
 
       // Upload each chunk
-      for (let i = 0; i < chunks.length; i++) {
-        // Calculate uploaded bytes
-        const uploadedBytes = chunks.slice(0, i).reduce((total, chunk) => total + chunk.size, 0);
+      for (let i = 0; i < ranges.length; i++) {
+        // Everything before this chunk has been uploaded
+        const [uploadedBytes, end] = ranges[i];
 
         // Update current chunk index and uploaded bytes
         this.updateQueue(recordingId, {
@@ -273,18 +273,28 @@ export class UploadManager {
           progress: 10 + Math.round((i / totalChunks) * 70)
         });
 
-        // Upload the chunk
-        await uploader.uploadChunk(chunks[i], i);
+        // Read and upload the chunk
+        const data = await invoke<ArrayBuffer>('read_recording_zip_chunk', {
+          recordingId,
+          offset: uploadedBytes,
+          length: end - uploadedBytes
+        });
+        await uploader.uploadChunk(new Blob([data], { type: 'application/zip' }), i);
 
         // After successful upload, add this chunk's size to uploaded bytes
         this.updateQueue(recordingId, {
-          uploadedBytes: uploadedBytes + chunks[i].size
+          uploadedBytes: end
         });
       }
 
       // Complete the upload
       const completeResult = await uploader.completeUpload();
 
+      // Lets the retention policy clean up uploaded recordings
+      await invoke('mark_recording_uploaded', { recordingId }).catch((error) =>
+        console.error('Failed to mark recording as uploaded:', error)
+      );
+
       // Update status to processing
       this.updateQueue(recordingId, {
         status: 'processing',
@@ -305,6 +315,11 @@ export class UploadManager {
       });
 
       return false;
+    } finally {
+      // The zip is rebuilt for every upload attempt
+      await invoke('delete_recording_zip', { recordingId }).catch((error) =>
+        console.error('Failed to delete recording zip:', error)
+      );
     }
   }
 